@@ -0,0 +1,146 @@
+use clap::Parser;
+use clr_common::{AppResult, Error};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+/// Tools that can be run through this binary
+pub const TOOLS: &[&str] = &[
+    "catr", "commr", "cutr", "echor", "findr", "grepr", "headr", "tailr", "uniqr", "wcr",
+];
+
+#[derive(Debug, Parser)]
+#[clap(
+    version = "0.1.0",
+    author = "akthrms <akt.hrms@gmail.com>",
+    about = "Rust multicall binary",
+    trailing_var_arg = true
+)]
+pub struct App {
+    /// Create a symlink to this binary for each tool in DIR
+    #[clap(long = "install", value_name = "DIR", conflicts_with = "tool")]
+    install: Option<PathBuf>,
+
+    /// Tool to run
+    #[clap(
+        value_name = "TOOL",
+        possible_values = TOOLS,
+        required_unless_present = "install"
+    )]
+    tool: Option<String>,
+
+    /// Arguments for the tool
+    #[clap(
+        value_name = "ARGS",
+        multiple_values = true,
+        allow_hyphen_values = true
+    )]
+    args: Vec<OsString>,
+}
+
+impl App {
+    pub fn run(self) -> AppResult<()> {
+        match (self.install, self.tool) {
+            (Some(dir), _) => install(&dir),
+            (None, Some(tool)) => {
+                let mut args = vec![OsString::from(&tool)];
+                args.extend(self.args);
+                dispatch(&tool, args)
+            }
+            (None, None) => unreachable!("clap requires TOOL without --install"),
+        }
+    }
+}
+
+/// Returns the tool invoked through `arg0`, e.g. a symlink named `catr`
+pub fn tool_name(arg0: &OsStr) -> Option<&'static str> {
+    let name = Path::new(arg0).file_stem()?;
+    TOOLS.iter().copied().find(|&tool| tool == name)
+}
+
+/// Returns the tool and its arguments, taking the tool name from `argv[0]`
+/// or, when invoked as `boxr TOOL [ARGS]...`, from `argv[1]`
+pub fn find_tool(args: &[OsString]) -> Option<(&'static str, &[OsString])> {
+    if let Some(tool) = args.first().and_then(|arg0| tool_name(arg0)) {
+        return Some((tool, args));
+    }
+
+    let arg1 = args.get(1)?;
+    TOOLS
+        .iter()
+        .copied()
+        .find(|&tool| tool == arg1)
+        .map(|tool| (tool, &args[1..]))
+}
+
+/// Runs `tool` with `args`, where `args[0]` is the program name
+pub fn dispatch(tool: &str, args: Vec<OsString>) -> AppResult<()> {
+    match tool {
+        "catr" => catr::run(catr::Config::parse_from(args)),
+        "commr" => commr::App::parse_from(args).run(),
+        "cutr" => cutr::App::parse_from(args).run(),
+        "echor" => echor::run(echor::Config::parse_from(args)),
+        "findr" => findr::App::parse_from(args).run(),
+        "grepr" => grepr::App::parse_from(args).run(),
+        "headr" => headr::App::parse_from(args).run(),
+        "tailr" => tailr::App::parse_from(args).run(),
+        "uniqr" => uniqr::App::parse_from(args).run(),
+        "wcr" => wcr::App::parse_from(args).run(),
+        _ => Err(Error::Usage(format!("unknown tool \"{}\"", tool))),
+    }
+}
+
+fn install(dir: &Path) -> AppResult<()> {
+    let exe = std::env::current_exe()?;
+
+    for tool in TOOLS {
+        let link = dir.join(tool);
+        let io_error = |e: io::Error| Error::Message(format!("{}: {}", link.display(), e));
+
+        if let Ok(metadata) = fs::symlink_metadata(&link) {
+            if !metadata.file_type().is_symlink() {
+                return Err(Error::Message(format!(
+                    "{}: exists and is not a symlink",
+                    link.display()
+                )));
+            }
+            fs::remove_file(&link).map_err(io_error)?;
+        }
+
+        symlink(&exe, &link).map_err(io_error)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_tool, tool_name};
+    use std::ffi::{OsStr, OsString};
+
+    #[test]
+    fn test_tool_name() {
+        assert_eq!(tool_name(OsStr::new("catr")), Some("catr"));
+        assert_eq!(tool_name(OsStr::new("/usr/local/bin/headr")), Some("headr"));
+        assert_eq!(tool_name(OsStr::new("./wcr")), Some("wcr"));
+        assert_eq!(tool_name(OsStr::new("boxr")), None);
+        assert_eq!(tool_name(OsStr::new("/bin/cat")), None);
+    }
+
+    #[test]
+    fn test_find_tool() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        let argv = args(&["/bin/catr", "-n"]);
+        assert_eq!(find_tool(&argv), Some(("catr", &argv[..])));
+
+        let argv = args(&["boxr", "catr", "-n"]);
+        assert_eq!(find_tool(&argv), Some(("catr", &argv[1..])));
+
+        assert_eq!(find_tool(&args(&["boxr", "./catr"])), None);
+        assert_eq!(find_tool(&args(&["boxr", "--install", "bin"])), None);
+        assert_eq!(find_tool(&args(&["boxr"])), None);
+    }
+}
@@ -0,0 +1,16 @@
+use boxr::{dispatch, find_tool, App};
+use clap::Parser;
+use std::env;
+
+fn main() {
+    let args = env::args_os().collect::<Vec<_>>();
+
+    let result = match find_tool(&args) {
+        Some((tool, args)) => dispatch(tool, args.to_vec()),
+        None => App::parse_from(args).run(),
+    };
+
+    if let Err(e) = result {
+        e.exit();
+    }
+}
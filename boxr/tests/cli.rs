@@ -0,0 +1,155 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+use tempfile::TempDir;
+
+const PROG: &str = "boxr";
+const TOOLS: &[&str] = &[
+    "catr", "commr", "cutr", "echor", "findr", "grepr", "headr", "tailr", "uniqr", "wcr",
+];
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+#[test]
+fn dies_no_tool() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("USAGE"));
+    Ok(())
+}
+
+#[test]
+fn dies_unknown_tool() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .arg("cat")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "\"cat\" isn't a valid value for '<TOOL>'",
+        ));
+    Ok(())
+}
+
+#[test]
+fn subcommand() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["echor", "-n", "Hello", "there"])
+        .assert()
+        .success()
+        .stdout("Hello there");
+    Ok(())
+}
+
+#[test]
+fn subcommand_stdin() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["wcr", "-l"])
+        .write_stdin("one\ntwo\n")
+        .assert()
+        .success()
+        .stdout("       2\n");
+    Ok(())
+}
+
+#[test]
+fn subcommand_help() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["headr", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rust head"));
+    Ok(())
+}
+
+#[test]
+fn subcommand_error_exit_code() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["commr", "-", "-"])
+        .assert()
+        .code(2)
+        .stderr("both input files cannot be STDIN (\"-\")\n");
+    Ok(())
+}
+
+#[test]
+fn install() -> TestResult {
+    let dir = TempDir::new()?;
+
+    for _ in 0..2 {
+        Command::cargo_bin(PROG)?
+            .arg("--install")
+            .arg(dir.path())
+            .assert()
+            .success();
+    }
+
+    for tool in TOOLS {
+        let link = dir.path().join(tool);
+        assert!(fs::symlink_metadata(&link)?.file_type().is_symlink());
+        assert_eq!(fs::read_link(&link)?, cargo_bin(PROG));
+    }
+
+    Command::new(dir.path().join("catr"))
+        .arg("-n")
+        .write_stdin("one\ntwo\n")
+        .assert()
+        .success()
+        .stdout("     1\tone\n     2\ttwo\n");
+
+    Command::new(dir.path().join("echor"))
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("echor"));
+
+    Ok(())
+}
+
+#[test]
+fn install_keeps_files() -> TestResult {
+    let dir = TempDir::new()?;
+    let file = dir.path().join("catr");
+    fs::write(&file, "keep")?;
+
+    Command::cargo_bin(PROG)?
+        .arg("--install")
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exists and is not a symlink"));
+
+    assert_eq!(fs::read_to_string(&file)?, "keep");
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["catr", "Cargo.toml"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
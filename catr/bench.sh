@@ -0,0 +1,36 @@
+#! /bin/bash
+
+# Compares catr with GNU cat when copying a large file.
+# Usage: ./bench.sh [SIZE_IN_MIB] (default 4096)
+
+set -eu
+
+SIZE_MIB="${1:-4096}"
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+
+INPUT="${WORK_DIR}/input.bin"
+OUTPUT="${WORK_DIR}/output.bin"
+
+cargo build --release --quiet
+CATR="$(pwd)/target/release/catr"
+
+echo "Creating ${SIZE_MIB} MiB input file"
+head -c "$((SIZE_MIB * 1024 * 1024))" /dev/urandom > "$INPUT"
+
+# Prints the wall-clock time of a command on stderr
+run() {
+    TIMEFORMAT="$(printf "%-28s" "$1") %R s"
+    shift
+    time "$@"
+}
+
+for CAT in cat "$CATR"; do
+    NAME=$(basename "$CAT")
+    run "$NAME > /dev/null" "$CAT" "$INPUT" > /dev/null
+    run "$NAME > file" "$CAT" "$INPUT" > "$OUTPUT"
+    cmp "$INPUT" "$OUTPUT"
+    run "$NAME | cat > /dev/null" bash -c "'$CAT' '$INPUT' | cat > /dev/null"
+    run "$NAME < file > file" bash -c "'$CAT' < '$INPUT' > '$OUTPUT'"
+    cmp "$INPUT" "$OUTPUT"
+done
@@ -24,6 +24,18 @@ cat $ALL > ${OUT_DIR}/all.out
 cat -n $ALL > ${OUT_DIR}/all.n.out
 cat -b $ALL > ${OUT_DIR}/all.b.out
 
+# GNU cat numbers lines across files, like catr --continuous
+cat -n $ALL > ${OUT_DIR}/all.n.continuous.out
+cat -b $ALL > ${OUT_DIR}/all.b.continuous.out
+
+nl -ba -v -2 -i 3 -w 3 -n rz -s ': ' $THE_BUSTLE > ${OUT_DIR}/$(basename "$THE_BUSTLE").nl.rz.out
+nl -ba -n ln -s '|' $THE_BUSTLE > ${OUT_DIR}/$(basename "$THE_BUSTLE").nl.ln.out
+
 cat < $THE_BUSTLE > ${OUT_DIR}/$(basename "$THE_BUSTLE").stdin.out
 cat -n < $THE_BUSTLE > ${OUT_DIR}/$(basename "$THE_BUSTLE").n.stdin.out
 cat -b < $THE_BUSTLE > ${OUT_DIR}/$(basename "$THE_BUSTLE").b.stdin.out
+
+CONTROL="${ROOT_DIR}/control.txt"
+for FLAG in A E T v s sn sb; do
+    cat -$FLAG $CONTROL > ${OUT_DIR}/$(basename "$CONTROL").${FLAG}.out
+done
@@ -0,0 +1,264 @@
+use crate::BUFFER_SIZE;
+use clr_common::{copy, parse_size, AppResult, Error, SizeError};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Suffix of the manifest written next to the chunks, in the format of
+/// `sha256sum`, so that `sha256sum -c` can also check the chunks
+pub(crate) const MANIFEST_SUFFIX: &str = "manifest";
+
+/// Writes its input to chunks of at most `size` bytes named `{prefix}000`,
+/// `{prefix}001` and so on, recording the SHA-256 checksum of each chunk
+pub(crate) struct Splitter {
+    prefix: String,
+    size: u64,
+    chunk: Option<Chunk>,
+    manifest: Vec<(String, String)>,
+}
+
+/// The chunk being written
+struct Chunk {
+    name: String,
+    file: BufWriter<File>,
+    hasher: Sha256,
+    len: u64,
+}
+
+impl Splitter {
+    pub(crate) fn new(prefix: &str, size: u64) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            size,
+            chunk: None,
+            manifest: vec![],
+        }
+    }
+
+    /// Closes the last chunk and writes the manifest listing the checksums of
+    /// all chunks in order
+    pub(crate) fn finish(mut self) -> AppResult<()> {
+        self.close()?;
+
+        let path = format!("{}{}", self.prefix, MANIFEST_SUFFIX);
+        let mut manifest = BufWriter::new(create(&path)?);
+        for (checksum, name) in &self.manifest {
+            writeln!(manifest, "{}  {}", checksum, name)?;
+        }
+        manifest.flush()?;
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        if let Some(mut chunk) = self.chunk.take() {
+            chunk.file.flush()?;
+            let checksum = format!("{:x}", chunk.hasher.finalize());
+            self.manifest.push((checksum, chunk.name));
+        }
+        Ok(())
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        self.close()?;
+
+        let path = format!("{}{:03}", self.prefix, self.manifest.len());
+        let file = File::create(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+        let name = file_name(&path);
+
+        self.chunk = Some(Chunk {
+            name,
+            file: BufWriter::with_capacity(BUFFER_SIZE, file),
+            hasher: Sha256::new(),
+            len: 0,
+        });
+        Ok(())
+    }
+}
+
+impl Write for Splitter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let size = self.size;
+        if self.chunk.as_ref().is_none_or(|chunk| chunk.len == size) {
+            self.next_chunk()?;
+        }
+        let chunk = self.chunk.as_mut().expect("a chunk is open");
+
+        let len = buf
+            .len()
+            .min((size - chunk.len).try_into().unwrap_or(usize::MAX));
+        let written = chunk.file.write(&buf[..len])?;
+        chunk.hasher.update(&buf[..written]);
+        chunk.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.chunk.as_mut() {
+            Some(chunk) => chunk.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Finds the manifest among `files` and returns the chunks it lists, in order,
+/// with their checksums. Chunks are taken from `files` by name, and otherwise
+/// from the directory of the manifest, so that the manifest alone is enough.
+fn read_manifest(files: &[String]) -> AppResult<Vec<(String, String)>> {
+    let (manifests, chunks): (Vec<_>, Vec<_>) = files
+        .iter()
+        .partition(|file| file_name(file).ends_with(MANIFEST_SUFFIX));
+    let path = match manifests[..] {
+        [path] => path,
+        [] => {
+            return Err(Error::Usage(format!(
+                "--join needs a manifest ending in \"{}\"",
+                MANIFEST_SUFFIX
+            )))
+        }
+        _ => return Err(Error::Usage("--join takes only one manifest".to_string())),
+    };
+
+    let text = fs::read_to_string(path).map_err(|source| Error::Input {
+        path: path.to_string(),
+        source,
+    })?;
+    let dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+    let mut listed = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let (checksum, name) = line
+            .split_once("  ")
+            .filter(|(checksum, _)| checksum.len() == 64)
+            .ok_or_else(|| format!("{}:{}: invalid manifest line", path, i + 1))?;
+
+        let chunk = match chunks.iter().find(|chunk| file_name(chunk) == name) {
+            Some(chunk) => chunk.to_string(),
+            None => dir.join(name).to_string_lossy().into_owned(),
+        };
+        listed.push((chunk, checksum.to_string()));
+    }
+
+    if let Some(chunk) = chunks
+        .iter()
+        .find(|chunk| !listed.iter().any(|(listed, _)| listed == **chunk))
+    {
+        return Err(format!("{}: not listed in {}", chunk, path).into());
+    }
+
+    Ok(listed)
+}
+
+/// Copies the chunks listed in the manifest among `files` to `out`, in the
+/// order of the manifest. Every chunk is checked against its checksum before
+/// any is copied, so that nothing is written unless all chunks are intact.
+pub(crate) fn join(files: &[String], out: &mut impl Write) -> AppResult<()> {
+    let chunks = read_manifest(files)?;
+    for (path, checksum) in &chunks {
+        verify(path, checksum)?;
+    }
+
+    for (path, _) in &chunks {
+        copy(path, &mut open(path)?, out)?;
+    }
+    Ok(())
+}
+
+/// Fails if the chunk at `path` does not match `checksum`
+fn verify(path: &str, checksum: &str) -> AppResult<()> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut open(path)?, &mut hasher).map_err(|source| Error::Input {
+        path: path.to_string(),
+        source,
+    })?;
+
+    match format!("{:x}", hasher.finalize()) == checksum {
+        true => Ok(()),
+        false => Err(format!("{}: checksum mismatch", path).into()),
+    }
+}
+
+fn open(path: &str) -> AppResult<File> {
+    File::open(path).map_err(|source| Error::Input {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// Parses a chunk size such as `512`, `64K`, `100M` or `1GB`, with the
+/// multiplier suffixes of `clr_common::parse_size`
+pub(crate) fn parse_split_size(val: &str) -> Result<u64, String> {
+    match parse_size(val) {
+        Ok(0) => Err(SizeError::Invalid.message("split size", val)),
+        Ok(size) => Ok(size),
+        Err(e) => Err(e.message("split size", val)),
+    }
+}
+
+fn create(path: &str) -> AppResult<File> {
+    File::create(path).map_err(|source| Error::Input {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_split_size, Splitter};
+    use std::fs;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_split_size() {
+        assert_eq!(parse_split_size("512"), Ok(512));
+        assert_eq!(parse_split_size("64K"), Ok(64 * 1024));
+        assert_eq!(parse_split_size("100M"), Ok(100 * 1024 * 1024));
+        assert_eq!(parse_split_size("1GB"), Ok(1_000_000_000));
+        assert!(parse_split_size("0").is_err());
+        assert!(parse_split_size("M").is_err());
+        assert!(parse_split_size("1X").is_err());
+        assert_eq!(
+            parse_split_size("99999999999T"),
+            Err("split size too large -- 99999999999T".to_string())
+        );
+    }
+
+    #[test]
+    fn test_splitter() {
+        let dir = TempDir::new().unwrap();
+        let prefix = dir.path().join("out.").to_string_lossy().into_owned();
+
+        let mut splitter = Splitter::new(&prefix, 4);
+        splitter.write_all(b"abcdefghij").unwrap();
+        splitter.finish().unwrap();
+
+        let read = |suffix: &str| fs::read_to_string(format!("{}{}", prefix, suffix)).unwrap();
+        assert_eq!(read("000"), "abcd");
+        assert_eq!(read("001"), "efgh");
+        assert_eq!(read("002"), "ij");
+        assert!(!dir.path().join("out.003").exists());
+
+        let manifest = read("manifest");
+        let names: Vec<_> = manifest
+            .lines()
+            .map(|line| line.split_once("  ").unwrap().1)
+            .collect();
+        assert_eq!(names, ["out.000", "out.001", "out.002"]);
+        assert!(manifest.starts_with(
+            "88d4266fd4e6338d13b845fcf289579d209c897823b9217da3e161936f031589  out.000\n"
+        ));
+    }
+}
@@ -0,0 +1,140 @@
+use clr_common::{AppResult, Error};
+use std::path::Path;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::as_24_bit_terminal_escaped;
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// The bundled syntax definitions and the selected theme
+pub(crate) struct Highlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+impl Highlighter {
+    pub(crate) fn new(theme: &str) -> AppResult<Self> {
+        let mut themes = ThemeSet::load_defaults().themes;
+        let theme = themes.remove(theme).ok_or_else(|| {
+            let mut names: Vec<_> = themes.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            Error::Usage(format!(
+                "unknown theme \"{}\", expected one of: {}",
+                theme,
+                names.join(", ")
+            ))
+        })?;
+
+        Ok(Self {
+            syntaxes: SyntaxSet::load_defaults_nonewlines(),
+            theme,
+        })
+    }
+
+    /// Starts highlighting `filename`, choosing the syntax by its extension
+    /// or, failing that, by its first line
+    pub(crate) fn file(&self, filename: &str, first_line: &str) -> FileHighlighter<'_> {
+        let syntax = self.syntax(filename, first_line);
+        FileHighlighter {
+            lines: HighlightLines::new(syntax, &self.theme),
+            syntaxes: &self.syntaxes,
+        }
+    }
+
+    fn syntax(&self, filename: &str, first_line: &str) -> &SyntaxReference {
+        let path = Path::new(filename);
+        let by_name = path.file_name().and_then(|name| {
+            self.syntaxes
+                .find_syntax_by_extension(&name.to_string_lossy())
+        });
+        let by_extension = || {
+            path.extension().and_then(|ext| {
+                self.syntaxes
+                    .find_syntax_by_extension(&ext.to_string_lossy())
+            })
+        };
+
+        by_name
+            .or_else(by_extension)
+            .or_else(|| self.syntaxes.find_syntax_by_first_line(first_line))
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text())
+    }
+}
+
+/// Highlights the lines of one file in order
+pub(crate) struct FileHighlighter<'a> {
+    lines: HighlightLines<'a>,
+    syntaxes: &'a SyntaxSet,
+}
+
+impl FileHighlighter<'_> {
+    /// Returns `line` with terminal escapes for its colors
+    pub(crate) fn highlight(&mut self, line: &str) -> AppResult<String> {
+        let ranges = self
+            .lines
+            .highlight_line(line, self.syntaxes)
+            .map_err(|e| Error::Message(e.to_string()))?;
+        Ok(format!(
+            "{}{}",
+            as_24_bit_terminal_escaped(&ranges, false),
+            RESET
+        ))
+    }
+}
+
+/// The file header printed before the lines of each file
+pub(crate) fn header(filename: &str) -> String {
+    format!("{}{}{}", BOLD, filename, RESET)
+}
+
+/// The gutter printed before each line, holding its line number if lines are
+/// numbered
+pub(crate) fn gutter(number: Option<&str>) -> String {
+    match number {
+        Some(number) => format!("{}{} │{} ", DIM, number, RESET),
+        None => format!("{}│{} ", DIM, RESET),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gutter, Highlighter};
+
+    #[test]
+    fn test_syntax() {
+        let highlighter = Highlighter::new("base16-ocean.dark").unwrap();
+        let name = |filename, first_line| highlighter.syntax(filename, first_line).name.clone();
+
+        assert_eq!(name("src/main.rs", ""), "Rust");
+        assert_eq!(name("Makefile", ""), "Makefile");
+        assert_eq!(name("-", "#!/bin/bash"), "Bourne Again Shell (bash)");
+        assert_eq!(name("notes.unknown", ""), "Plain Text");
+    }
+
+    #[test]
+    fn test_highlight() {
+        let highlighter = Highlighter::new("base16-ocean.dark").unwrap();
+        let mut file = highlighter.file("main.rs", "fn main() {}");
+        let line = file.highlight("fn main() {}").unwrap();
+
+        assert!(line.starts_with("\x1b[38;2;"));
+        assert!(line.contains("main"));
+        assert!(line.ends_with("\x1b[0m"));
+    }
+
+    #[test]
+    fn test_unknown_theme() {
+        let err = Highlighter::new("nope").err().unwrap();
+        assert!(err.to_string().starts_with("unknown theme \"nope\""));
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_gutter() {
+        assert_eq!(gutter(Some("     1")), "\x1b[2m     1 │\x1b[0m ");
+        assert_eq!(gutter(None), "\x1b[2m│\x1b[0m ");
+    }
+}
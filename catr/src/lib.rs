@@ -1,9 +1,67 @@
+mod chunk;
+mod highlight;
+
+use chunk::Splitter;
 use clap::Parser;
-use std::error::Error;
+use clr_common::{copy, decompress, open_input, report, terminator, AppResult, Error, Named};
+use highlight::Highlighter;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::str::FromStr;
+
+/// Output buffer size, large enough that copying is not dominated by system
+/// calls when the kernel cannot copy between the files directly
+const BUFFER_SIZE: usize = 128 * 1024;
+
+/// Prefix of a pseudo-filename naming a Unix domain socket to read from
+const SOCKET_PREFIX: &str = "unix:";
+
+/// Justification of line numbers, as in `nl -n`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberFormat {
+    /// Left justified
+    Left,
+    /// Right justified, padded with spaces
+    Right,
+    /// Right justified, padded with zeros
+    Zero,
+}
 
-type AppResult<T> = Result<T, Box<dyn Error>>;
+impl FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ln" => Ok(Self::Left),
+            "rn" => Ok(Self::Right),
+            "rz" => Ok(Self::Zero),
+            _ => Err(format!("invalid line numbering format: {}", s)),
+        }
+    }
+}
+
+/// When to use colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorWhen {
+    /// Only if standard output is a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorWhen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!("invalid color setting: {}", s)),
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -12,7 +70,7 @@ type AppResult<T> = Result<T, Box<dyn Error>>;
     about = "Rust cat"
 )]
 pub struct Config {
-    /// Input file(s)
+    /// Input file(s). unix:PATH reads from the Unix domain socket at PATH.
     #[clap(value_name = "FILE", multiple_occurrences = true, default_value = "-")]
     files: Vec<String>,
 
@@ -28,38 +86,475 @@ pub struct Config {
     /// Number non-blank lines
     #[clap(short = 'b', long = "number-nonblank", takes_value = false)]
     number_nonblank_lines: bool,
+
+    /// Continue line numbers across files instead of restarting them
+    #[clap(long = "continuous", takes_value = false)]
+    continuous: bool,
+
+    /// First line number
+    #[clap(
+        long = "starting-line-number",
+        value_name = "NUMBER",
+        default_value_t = 1,
+        allow_hyphen_values = true
+    )]
+    starting_line_number: i64,
+
+    /// Line number increment
+    #[clap(
+        long = "line-increment",
+        value_name = "NUMBER",
+        default_value_t = 1,
+        allow_hyphen_values = true
+    )]
+    line_increment: i64,
+
+    /// Width of line numbers
+    #[clap(
+        long = "number-width",
+        value_name = "NUMBER",
+        default_value_t = 6,
+        parse(try_from_str = parse_width)
+    )]
+    number_width: usize,
+
+    /// Separator between line numbers and lines
+    #[clap(long = "number-separator", value_name = "STRING", default_value = "\t")]
+    number_separator: String,
+
+    /// Line number format: left justified (ln), right justified (rn) or right
+    /// justified with leading zeros (rz)
+    #[clap(
+        long = "number-format",
+        value_name = "FORMAT",
+        default_value = "rn",
+        possible_values = &["ln", "rn", "rz"]
+    )]
+    number_format: NumberFormat,
+
+    /// Equivalent to -vET
+    #[clap(short = 'A', long = "show-all", takes_value = false)]
+    show_all: bool,
+
+    /// Display $ at the end of each line
+    #[clap(short = 'E', long = "show-ends", takes_value = false)]
+    show_ends: bool,
+
+    /// Display TAB characters as ^I
+    #[clap(short = 'T', long = "show-tabs", takes_value = false)]
+    show_tabs: bool,
+
+    /// Use ^ and M- notation, except for LFD and TAB
+    #[clap(short = 'v', long = "show-nonprinting", takes_value = false)]
+    show_nonprinting: bool,
+
+    /// Suppress repeated empty output lines
+    #[clap(short = 's', long = "squeeze-blank", takes_value = false)]
+    squeeze_blank: bool,
+
+    /// Highlight syntax by file extension, with a header for each file and
+    /// line numbers in a gutter
+    #[clap(short = 'H', long = "highlight", takes_value = false)]
+    highlight: bool,
+
+    /// When to highlight: auto (only if standard output is a terminal),
+    /// always or never
+    #[clap(
+        long = "color",
+        value_name = "WHEN",
+        default_value = "auto",
+        possible_values = &["auto", "always", "never"]
+    )]
+    color: ColorWhen,
+
+    /// Syntax highlighting theme
+    #[clap(
+        long = "theme",
+        value_name = "THEME",
+        default_value = "base16-ocean.dark"
+    )]
+    theme: String,
+
+    /// Decompress gzip, bzip2, xz and zstd input
+    #[clap(short = 'Z', long = "decompress", takes_value = false)]
+    decompress: bool,
+
+    /// Split and write records on NUL instead of newline
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
+
+    /// Write the output to chunks of SIZE bytes, with an optional multiplier
+    /// suffix such as K or MiB, named PREFIX000, PREFIX001, ... with a
+    /// PREFIXmanifest of their SHA-256 checksums
+    #[clap(
+        long = "split-size",
+        value_name = "SIZE",
+        parse(try_from_str = chunk::parse_split_size),
+        conflicts_with_all = &["join", "highlight"]
+    )]
+    split_size: Option<u64>,
+
+    /// Name prefix of the chunks written by --split-size
+    #[clap(
+        long = "prefix",
+        value_name = "PREFIX",
+        default_value = "x",
+        requires = "split-size"
+    )]
+    prefix: String,
+
+    /// Reassemble the chunks listed in the manifest among the input files,
+    /// in the order of the manifest. Nothing is written unless the checksums
+    /// of all chunks match.
+    #[clap(
+        long = "join",
+        takes_value = false,
+        conflicts_with_all = &[
+            "number-lines",
+            "number-nonblank-lines",
+            "show-all",
+            "show-ends",
+            "show-tabs",
+            "show-nonprinting",
+            "squeeze-blank",
+            "highlight",
+            "decompress",
+        ]
+    )]
+    join: bool,
+}
+
+/// An input file. Files that are copied unchanged are not buffered, so that
+/// `io::copy` can use `copy_file_range`, `sendfile` or `splice` on Linux.
+enum Input {
+    Stdin,
+    File(File),
+    Socket(UnixStream),
+    Buffered(Box<dyn BufRead>),
 }
 
-pub fn run(config: Config) -> AppResult<()> {
-    for filename in config.files {
-        match open(&filename) {
-            Err(e) => eprintln!("Failed to open {}: {}", filename, e),
-            Ok(file) => {
-                let mut nonblank_line_num = 0;
-                for (line_num, line) in file.lines().enumerate() {
-                    let line = line?;
-                    if config.number_lines {
-                        println!("{:>6}\t{}", line_num + 1, line);
-                    } else if config.number_nonblank_lines {
-                        if line.is_empty() {
-                            println!("");
-                        } else {
-                            nonblank_line_num += 1;
-                            println!("{:>6}\t{}", nonblank_line_num, line);
-                        }
-                    } else {
-                        println!("{}", line);
-                    }
+pub fn run(mut config: Config) -> AppResult<()> {
+    if config.show_all {
+        config.show_nonprinting = true;
+        config.show_ends = true;
+        config.show_tabs = true;
+    }
+
+    config.highlight &= match config.color {
+        ColorWhen::Auto => io::stdout().is_terminal(),
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+    };
+    let highlighter = match config.highlight {
+        true => Some(Highlighter::new(&config.theme)?),
+        false => None,
+    };
+
+    let failed = match config.split_size {
+        Some(size) => {
+            let mut splitter = Splitter::new(&config.prefix, size);
+            let failed = config.cat_files(highlighter.as_ref(), &mut splitter)?;
+            splitter.finish()?;
+            failed
+        }
+        None => {
+            let mut out = BufWriter::with_capacity(BUFFER_SIZE, io::stdout().lock());
+            let failed = config.cat_files(highlighter.as_ref(), &mut out)?;
+            out.flush()?;
+            failed
+        }
+    };
+
+    if failed {
+        return Err(Error::Exit(1));
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Copies all input files to `out`, or with `--join` the chunks listed in
+    /// the manifest. Returns whether any file failed.
+    fn cat_files(
+        &self,
+        highlighter: Option<&Highlighter>,
+        out: &mut impl Write,
+    ) -> AppResult<bool> {
+        if self.join {
+            chunk::join(&self.files, out)?;
+            return Ok(false);
+        }
+
+        let mut line_num = self.starting_line_number;
+        let mut failed = false;
+
+        for filename in &self.files {
+            if !self.continuous {
+                line_num = self.starting_line_number;
+            }
+
+            failed |= report(self.cat_file(filename, highlighter, &mut line_num, out))?;
+        }
+
+        Ok(failed)
+    }
+
+    /// Copies one input file to `out`. Errors reading the file are
+    /// `Error::Input` errors.
+    fn cat_file(
+        &self,
+        filename: &str,
+        highlighter: Option<&Highlighter>,
+        line_num: &mut i64,
+        out: &mut impl Write,
+    ) -> AppResult<()> {
+        match self.open(filename)? {
+            Input::Stdin => self.copy(filename, &mut io::stdin().lock(), out)?,
+            Input::File(mut file) => self.copy(filename, &mut file, out)?,
+            Input::Socket(mut stream) => self.copy(filename, &mut stream, out)?,
+            Input::Buffered(mut file) if self.is_plain() => self.copy(filename, &mut file, out)?,
+            Input::Buffered(file) => {
+                let file = Named::new(filename, file);
+                self.cat(file, filename, highlighter, line_num, out)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies `file` to `out` unchanged. Chunks written by --split-size are
+    /// not files that `io::copy` can copy to directly, so a failed read and a
+    /// failed write can be told apart, and a chunk that cannot be written
+    /// ends the run rather than being blamed on the input.
+    fn copy(&self, filename: &str, file: &mut impl Read, out: &mut impl Write) -> AppResult<()> {
+        match self.split_size {
+            Some(_) => io::copy(&mut Named::new(filename, file), out)?,
+            None => copy(filename, file, out)?,
+        };
+        Ok(())
+    }
+
+    /// Whether the input is copied unchanged
+    fn is_plain(&self) -> bool {
+        !(self.number_lines
+            || self.number_nonblank_lines
+            || self.show_ends
+            || self.show_tabs
+            || self.show_nonprinting
+            || self.squeeze_blank
+            || self.highlight)
+    }
+
+    /// Opens a file, standard input or a socket. FIFOs and `/dev/fd` files
+    /// such as those from process substitution are opened like any other
+    /// file, so opening a FIFO waits for its writer and the files are still
+    /// read one after another in order.
+    fn open(&self, filename: &str) -> AppResult<Input> {
+        let input_error = |source| Error::Input {
+            path: filename.to_string(),
+            source,
+        };
+
+        if let Some(path) = filename.strip_prefix(SOCKET_PREFIX) {
+            let stream = UnixStream::connect(path).map_err(input_error)?;
+            if self.is_plain() && !self.decompress {
+                return Ok(Input::Socket(stream));
+            }
+
+            let file: Box<dyn BufRead> = Box::new(BufReader::new(stream));
+            return match self.decompress {
+                true => decompress(file).map(Input::Buffered).map_err(input_error),
+                false => Ok(Input::Buffered(file)),
+            };
+        }
+
+        if !self.is_plain() || self.decompress {
+            return open_input(filename, self.decompress).map(Input::Buffered);
+        }
+
+        match filename {
+            "-" => Ok(Input::Stdin),
+            _ => File::open(filename).map(Input::File).map_err(input_error),
+        }
+    }
+
+    /// Copies `file` to `out` one line at a time, applying the numbering and
+    /// display options to the raw bytes of each line. `line_num` is the number
+    /// of the next numbered line. With a `highlighter`, lines are highlighted
+    /// according to the syntax of `filename`.
+    fn cat(
+        &self,
+        mut file: impl BufRead,
+        filename: &str,
+        highlighter: Option<&Highlighter>,
+        line_num: &mut i64,
+        out: &mut impl Write,
+    ) -> AppResult<()> {
+        let terminator = terminator(self.null_data);
+        let numbering = self.number_lines || self.number_nonblank_lines;
+        let mut line = vec![];
+        let mut visible = vec![];
+        let mut prev_blank = false;
+        let mut file_highlighter = None;
+
+        if highlighter.is_some() {
+            writeln!(out, "{}", highlight::header(filename))?;
+        }
+
+        loop {
+            line.clear();
+            if file.read_until(terminator, &mut line)? == 0 {
+                break;
+            }
+
+            let (mut content, ended) = match line.strip_suffix(&[terminator]) {
+                Some(content) => (content, true),
+                None => (&line[..], false),
+            };
+
+            let blank = content.is_empty();
+            if self.squeeze_blank && blank && prev_blank {
+                continue;
+            }
+            prev_blank = blank;
+
+            let number = if self.number_lines || (self.number_nonblank_lines && !blank) {
+                let number = self.format_number(*line_num);
+                *line_num = line_num
+                    .checked_add(self.line_increment)
+                    .ok_or("line number overflow")?;
+                Some(number)
+            } else {
+                None
+            };
+
+            match (highlighter, &number) {
+                (Some(_), None) if numbering => {
+                    let padding = " ".repeat(self.number_width);
+                    write!(out, "{}", highlight::gutter(Some(&padding)))?;
                 }
+                (Some(_), number) => write!(out, "{}", highlight::gutter(number.as_deref()))?,
+                (None, Some(number)) => write!(out, "{}{}", number, self.number_separator)?,
+                (None, None) => (),
             }
+
+            // Like GNU cat, -E shows the \r of a \r\n line ending as ^M
+            let crlf = self.show_ends && ended && content.ends_with(b"\r");
+            if crlf {
+                content = &content[..content.len() - 1];
+            }
+
+            if self.show_tabs || self.show_nonprinting {
+                visible.clear();
+                for &byte in content {
+                    push_visible(byte, self.show_tabs, self.show_nonprinting, &mut visible);
+                }
+                content = &visible;
+            }
+
+            match highlighter {
+                Some(highlighter) => {
+                    let text = String::from_utf8_lossy(content);
+                    let file_highlighter =
+                        file_highlighter.get_or_insert_with(|| highlighter.file(filename, &text));
+                    out.write_all(file_highlighter.highlight(&text)?.as_bytes())?;
+                }
+                None => out.write_all(content)?,
+            }
+
+            if crlf {
+                out.write_all(b"^M")?;
+            }
+            if ended {
+                if self.show_ends {
+                    out.write_all(b"$")?;
+                }
+                out.write_all(&[terminator])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn format_number(&self, num: i64) -> String {
+        let width = self.number_width;
+        match self.number_format {
+            NumberFormat::Left => format!("{:<width$}", num),
+            NumberFormat::Right => format!("{:>width$}", num),
+            NumberFormat::Zero => format!("{:0width$}", num),
         }
     }
-    Ok(())
 }
 
-fn open(filename: &str) -> AppResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn parse_width(val: &str) -> Result<usize, String> {
+    match val.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("invalid line number field width -- {}", val)),
+    }
+}
+
+/// Appends `byte` to `visible`, with tabs as `^I` if `show_tabs`, and other
+/// control characters as `^X` and bytes above 127 as `M-x` if
+/// `show_nonprinting`. Newlines are always left as they are.
+fn push_visible(byte: u8, show_tabs: bool, show_nonprinting: bool, visible: &mut Vec<u8>) {
+    match byte {
+        b'\t' if show_tabs => visible.extend_from_slice(b"^I"),
+        b'\t' | b'\n' => visible.push(byte),
+        _ if !show_nonprinting => visible.push(byte),
+        128.. => {
+            visible.extend_from_slice(b"M-");
+            match byte - 128 {
+                byte @ 0..=31 => visible.extend_from_slice(&[b'^', byte + 64]),
+                127 => visible.extend_from_slice(b"^?"),
+                byte => visible.push(byte),
+            }
+        }
+        0..=31 => visible.extend_from_slice(&[b'^', byte + 64]),
+        127 => visible.extend_from_slice(b"^?"),
+        _ => visible.push(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_width, push_visible, Config};
+    use clap::Parser;
+
+    #[test]
+    fn test_format_number() {
+        let config = |format: &str| {
+            Config::parse_from(["catr", "--number-width", "4", "--number-format", format])
+        };
+
+        assert_eq!(config("ln").format_number(12), "12  ");
+        assert_eq!(config("rn").format_number(12), "  12");
+        assert_eq!(config("rz").format_number(12), "0012");
+        assert_eq!(config("rz").format_number(-12), "-012");
+        assert_eq!(config("rn").format_number(123456), "123456");
+    }
+
+    #[test]
+    fn test_parse_width() {
+        assert_eq!(parse_width("3"), Ok(3));
+        assert!(parse_width("0").is_err());
+        assert!(parse_width("-1").is_err());
+        assert!(parse_width("foo").is_err());
+    }
+
+    fn visible(bytes: &[u8], show_tabs: bool, show_nonprinting: bool) -> String {
+        let mut visible = vec![];
+        for &byte in bytes {
+            push_visible(byte, show_tabs, show_nonprinting, &mut visible);
+        }
+        String::from_utf8(visible).unwrap()
+    }
+
+    #[test]
+    fn test_push_visible() {
+        assert_eq!(visible(b"a\tb", false, false), "a\tb");
+        assert_eq!(visible(b"a\tb", true, false), "a^Ib");
+        assert_eq!(visible(b"a\tb\n", false, true), "a\tb\n");
+        assert_eq!(visible(b"\0\x07\x1b\x7f", false, true), "^@^G^[^?");
+        assert_eq!(visible(b"\x07", true, false), "\x07");
+        assert_eq!(visible("\u{2014}".as_bytes(), false, true), "M-bM-^@M-^T");
+        assert_eq!(visible(b"\x89\x8a\xa0\xff", true, true), "M-^IM-^JM- M-^?");
     }
 }
@@ -3,7 +3,6 @@ use clap::Parser;
 
 fn main() {
     if let Err(e) = run(Config::parse()) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        e.exit();
     }
 }
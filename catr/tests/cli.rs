@@ -1,14 +1,22 @@
-use assert_cmd::Command;
+use assert_cmd::{cargo::cargo_bin, Command};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::error::Error;
-use std::fs;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::net::UnixListener;
+use std::process::{Command as StdCommand, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tempfile::TempDir;
 
 const PROG: &str = "catr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const THE_BUSTLE: &str = "tests/inputs/the_bustle.txt";
+const CONTROL: &str = "tests/inputs/control.txt";
+const HELLO: &str = "tests/inputs/hello.rs";
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -45,18 +53,50 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PROG)?
         .arg(&bad)
         .assert()
-        .success()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+#[test]
+fn bad_file_among_good_files() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string(FOX)?;
+
+    Command::cargo_bin(PROG)?
+        .args([&bad, FOX])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(expected))
+        .stderr(predicate::str::starts_with(format!("{}: ", bad)));
+
+    Ok(())
+}
+
+#[test]
+fn directory_among_good_files() -> TestResult {
+    for (args, expected_file) in [
+        (&["tests/inputs", FOX][..], "tests/expected/fox.txt.out"),
+        (&["-n", "tests/inputs", FOX], "tests/expected/fox.txt.n.out"),
+    ] {
+        Command::cargo_bin(PROG)?
+            .args(args)
+            .assert()
+            .code(1)
+            .stdout(predicate::eq(fs::read(expected_file)?))
+            .stderr(predicate::str::starts_with("tests/inputs: "));
+    }
+
+    Ok(())
+}
+
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    let expected = fs::read_to_string(expected_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PROG)?
         .args(args)
         .assert()
         .success()
-        .stdout(expected);
+        .stdout(predicate::eq(expected));
     Ok(())
 }
 
@@ -141,6 +181,105 @@ fn all_b() -> TestResult {
     )
 }
 
+#[test]
+fn control_show_all() -> TestResult {
+    run(&["-A", CONTROL], "tests/expected/control.txt.A.out")
+}
+
+#[test]
+fn control_show_ends() -> TestResult {
+    run(&["-E", CONTROL], "tests/expected/control.txt.E.out")
+}
+
+#[test]
+fn control_show_tabs() -> TestResult {
+    run(&["-T", CONTROL], "tests/expected/control.txt.T.out")
+}
+
+#[test]
+fn control_show_nonprinting() -> TestResult {
+    run(&["-v", CONTROL], "tests/expected/control.txt.v.out")
+}
+
+#[test]
+fn control_squeeze_blank() -> TestResult {
+    run(&["-s", CONTROL], "tests/expected/control.txt.s.out")
+}
+
+#[test]
+fn control_squeeze_blank_n() -> TestResult {
+    run(&["-sn", CONTROL], "tests/expected/control.txt.sn.out")
+}
+
+#[test]
+fn control_squeeze_blank_b() -> TestResult {
+    run(&["-sb", CONTROL], "tests/expected/control.txt.sb.out")
+}
+
+#[test]
+fn all_n_continuous() -> TestResult {
+    run(
+        &["-n", "--continuous", EMPTY, FOX, SPIDERS, THE_BUSTLE],
+        "tests/expected/all.n.continuous.out",
+    )
+}
+
+#[test]
+fn all_b_continuous() -> TestResult {
+    run(
+        &["-b", "--continuous", EMPTY, FOX, SPIDERS, THE_BUSTLE],
+        "tests/expected/all.b.continuous.out",
+    )
+}
+
+#[test]
+fn the_bustle_nl_rz() -> TestResult {
+    run(
+        &[
+            "-n",
+            "--starting-line-number",
+            "-2",
+            "--line-increment",
+            "3",
+            "--number-width",
+            "3",
+            "--number-format",
+            "rz",
+            "--number-separator",
+            ": ",
+            THE_BUSTLE,
+        ],
+        "tests/expected/the_bustle.txt.nl.rz.out",
+    )
+}
+
+#[test]
+fn the_bustle_nl_ln() -> TestResult {
+    run(
+        &[
+            "-n",
+            "--number-format",
+            "ln",
+            "--number-separator",
+            "|",
+            THE_BUSTLE,
+        ],
+        "tests/expected/the_bustle.txt.nl.ln.out",
+    )
+}
+
+#[test]
+fn bad_number_width() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-n", "--number-width", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid line number field width -- 0",
+        ));
+    Ok(())
+}
+
 fn run_stdin(input_file: &str, args: &[&str], expected_file: &str) -> TestResult {
     let input = fs::read_to_string(input_file)?;
     let expected = fs::read_to_string(expected_file)?;
@@ -179,3 +318,427 @@ fn the_bustle_stdin_b() -> TestResult {
         "tests/expected/the_bustle.txt.b.stdin.out",
     )
 }
+
+#[test]
+fn decompress() -> TestResult {
+    for ext in ["gz", "bz2", "xz", "zst"] {
+        let file = format!("{}.{}", THE_BUSTLE, ext);
+        run(&["-Z", &file], "tests/expected/the_bustle.txt.out")?;
+        run(&["-Z", "-n", &file], "tests/expected/the_bustle.txt.n.out")?;
+    }
+    Ok(())
+}
+
+#[test]
+fn decompress_stdin() -> TestResult {
+    let input = fs::read(format!("{}.gz", THE_BUSTLE))?;
+    Command::cargo_bin(PROG)?
+        .args(["--decompress"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/expected/the_bustle.txt.out")?);
+    Ok(())
+}
+
+#[test]
+fn decompress_plain_text() -> TestResult {
+    run(&["-Z", THE_BUSTLE], "tests/expected/the_bustle.txt.out")
+}
+
+#[test]
+fn decompress_corrupt() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-Z", "-"])
+        .write_stdin(vec![0x1f, 0x8b, 0x00, 0x00])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_empty().not());
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args([FOX])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn null_data() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z"])
+        .write_stdin("a\nb\0\0c")
+        .assert()
+        .success()
+        .stdout("a\nb\0\0c");
+    Ok(())
+}
+
+#[test]
+fn null_data_n() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-n"])
+        .write_stdin("a\nb\0\0c\0")
+        .assert()
+        .success()
+        .stdout("     1\ta\nb\0     2\t\0     3\tc\0");
+    Ok(())
+}
+
+#[test]
+fn null_data_b() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--null-data", "-b"])
+        .write_stdin("a\0\0c\0")
+        .assert()
+        .success()
+        .stdout("     1\ta\0\0     2\tc\0");
+    Ok(())
+}
+
+#[test]
+fn show_all_long() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--show-all", "--squeeze-blank"])
+        .write_stdin("a\tb\r\n\n\n\x01\n")
+        .assert()
+        .success()
+        .stdout("a^Ib^M$\n$\n^A$\n");
+    Ok(())
+}
+
+#[test]
+fn binary() -> TestResult {
+    run(&[CONTROL], CONTROL)
+}
+
+#[test]
+fn binary_stdin() -> TestResult {
+    let input = fs::read(CONTROL)?;
+    Command::cargo_bin(PROG)?
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(predicate::eq(input));
+    Ok(())
+}
+
+#[test]
+fn binary_among_files() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args([CONTROL, "-", FOX])
+        .write_stdin(vec![0xff, 0xfe])
+        .assert()
+        .success()
+        .stdout(predicate::eq(
+            [&fs::read(CONTROL)?[..], &[0xff, 0xfe], &fs::read(FOX)?[..]].concat(),
+        ));
+    Ok(())
+}
+
+#[test]
+fn highlight_not_a_terminal() -> TestResult {
+    run(&["-H", HELLO], HELLO)
+}
+
+#[test]
+fn highlight_never() -> TestResult {
+    run(
+        &["--highlight", "--color", "never", "-n", FOX],
+        "tests/expected/fox.txt.n.out",
+    )
+}
+
+#[test]
+fn highlight_always() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-H", "--color", "always", HELLO])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "\x1b[1m{}\x1b[0m\n",
+            HELLO
+        )))
+        .stdout(predicate::str::contains("\x1b[2m│\x1b[0m \x1b[38;2;"))
+        .stdout(predicate::str::contains("Hello, world!"));
+    Ok(())
+}
+
+#[test]
+fn highlight_numbered() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-H", "--color", "always", "-b", HELLO, THE_BUSTLE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[2m     3 │\x1b[0m "))
+        .stdout(predicate::str::contains(format!(
+            "\x1b[1m{}\x1b[0m\n",
+            THE_BUSTLE
+        )))
+        .stdout(predicate::str::contains("\x1b[2m       │\x1b[0m "));
+    Ok(())
+}
+
+#[test]
+fn highlight_bad_theme() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-H", "--color", "always", "--theme", "nope", HELLO])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::starts_with("unknown theme \"nope\""));
+    Ok(())
+}
+
+/// Serves `chunks` to the first client of a Unix domain socket in a new
+/// directory, pausing between chunks so that the client sees partial reads.
+/// Returns the directory, the `unix:` name of the socket and the server.
+fn serve(
+    chunks: &'static [&'static str],
+) -> io::Result<(TempDir, String, JoinHandle<io::Result<()>>)> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("socket");
+    let listener = UnixListener::bind(&path)?;
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept()?;
+        for chunk in chunks {
+            stream.write_all(chunk.as_bytes())?;
+            thread::sleep(Duration::from_millis(50));
+        }
+        Ok(())
+    });
+
+    Ok((dir, format!("unix:{}", path.display()), server))
+}
+
+#[test]
+fn unix_socket() -> TestResult {
+    let (_dir, name, server) = serve(&["one\ntw", "o\nthr", "ee\n"])?;
+
+    Command::cargo_bin(PROG)?
+        .args(["-n", &name])
+        .assert()
+        .success()
+        .stdout("     1\tone\n     2\ttwo\n     3\tthree\n");
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn unix_socket_among_files() -> TestResult {
+    let (_dir, name, server) = serve(&["from ", "socket\n"])?;
+    let expected = format!(
+        "{}from socket\n{}",
+        fs::read_to_string(FOX)?,
+        fs::read_to_string(SPIDERS)?
+    );
+
+    Command::cargo_bin(PROG)?
+        .args([FOX, &name, SPIDERS])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    server.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn unix_socket_missing() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["unix:tests/inputs/no-such-socket", FOX])
+        .assert()
+        .code(1)
+        .stdout(fs::read_to_string(FOX)?)
+        .stderr(predicate::str::starts_with(
+            "unix:tests/inputs/no-such-socket: No such file or directory",
+        ));
+    Ok(())
+}
+
+#[test]
+fn fifo() -> TestResult {
+    let dir = TempDir::new()?;
+    let fifo = dir.path().join("fifo");
+    assert!(StdCommand::new("mkfifo").arg(&fifo).status()?.success());
+
+    let writer = {
+        let fifo = fifo.clone();
+        thread::spawn(move || -> io::Result<()> {
+            thread::sleep(Duration::from_millis(100));
+            let mut file = OpenOptions::new().write(true).open(fifo)?;
+            file.write_all(b"from\n")?;
+            thread::sleep(Duration::from_millis(50));
+            file.write_all(b"fifo\n")
+        })
+    };
+
+    let expected = format!(
+        "{}from\nfifo\n{}",
+        fs::read_to_string(FOX)?,
+        fs::read_to_string(SPIDERS)?
+    );
+
+    Command::cargo_bin(PROG)?
+        .args([FOX, fifo.to_str().unwrap(), SPIDERS])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    writer.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn process_substitution() -> TestResult {
+    let script = format!(
+        "'{}' -n <(printf 'a\\nb\\n') {}",
+        cargo_bin(PROG).display(),
+        FOX
+    );
+
+    let output = StdCommand::new("bash").args(["-c", &script]).output()?;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        format!("     1\ta\n     2\tb\n     1\t{}", fs::read_to_string(FOX)?)
+    );
+    Ok(())
+}
+
+fn split(dir: &TempDir, size: &str) -> Result<String, Box<dyn Error>> {
+    let prefix = dir.path().join("out.").to_string_lossy().into_owned();
+    Command::cargo_bin(PROG)?
+        .args(["--split-size", size, "--prefix", &prefix, THE_BUSTLE, FOX])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(prefix)
+}
+
+#[test]
+fn split_size() -> TestResult {
+    let dir = TempDir::new()?;
+    let prefix = split(&dir, "100")?;
+
+    let mut expected = fs::read(THE_BUSTLE)?;
+    expected.extend(fs::read(FOX)?);
+    for (i, chunk) in expected.chunks(100).enumerate() {
+        assert_eq!(fs::read(format!("{}{:03}", prefix, i))?, chunk);
+    }
+    assert!(!dir.path().join("out.003").exists());
+
+    let manifest = fs::read_to_string(format!("{}manifest", prefix))?;
+    let names: Vec<_> = manifest.lines().map(|line| &line[66..]).collect();
+    assert_eq!(names, ["out.000", "out.001", "out.002"]);
+    Ok(())
+}
+
+#[test]
+fn split_size_bad_prefix() -> TestResult {
+    let dir = TempDir::new()?;
+    let prefix = dir
+        .path()
+        .join("missing/out.")
+        .to_string_lossy()
+        .into_owned();
+
+    Command::cargo_bin(PROG)?
+        .args(["--split-size", "10", "--prefix", &prefix, FOX, SPIDERS])
+        .assert()
+        .code(1)
+        .stderr(format!(
+            "{}000: No such file or directory (os error 2)\n",
+            prefix
+        ));
+    Ok(())
+}
+
+#[test]
+fn join() -> TestResult {
+    let dir = TempDir::new()?;
+    let prefix = split(&dir, "100")?;
+    let mut expected = fs::read(THE_BUSTLE)?;
+    expected.extend(fs::read(FOX)?);
+
+    // Chunks are joined in the order of the manifest, not of the arguments
+    let chunk = |suffix: &str| format!("{}{}", prefix, suffix);
+    Command::cargo_bin(PROG)?
+        .args([
+            "--join",
+            &chunk("002"),
+            &chunk("manifest"),
+            &chunk("000"),
+            &chunk("001"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq(&expected[..]));
+
+    // The manifest alone finds the chunks next to it
+    Command::cargo_bin(PROG)?
+        .args(["--join", &chunk("manifest")])
+        .assert()
+        .success()
+        .stdout(predicate::eq(&expected[..]));
+    Ok(())
+}
+
+#[test]
+fn join_checksum_mismatch() -> TestResult {
+    let dir = TempDir::new()?;
+    let prefix = split(&dir, "100")?;
+    OpenOptions::new()
+        .append(true)
+        .open(format!("{}001", prefix))?
+        .write_all(b"!")?;
+
+    Command::cargo_bin(PROG)?
+        .args(["--join", &format!("{}manifest", prefix)])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(format!("{}001: checksum mismatch\n", prefix));
+    Ok(())
+}
+
+#[test]
+fn join_unlisted_chunk() -> TestResult {
+    let dir = TempDir::new()?;
+    let prefix = split(&dir, "100")?;
+
+    Command::cargo_bin(PROG)?
+        .args(["--join", &format!("{}manifest", prefix), FOX])
+        .assert()
+        .failure()
+        .stderr(format!("{}: not listed in {}manifest\n", FOX, prefix));
+
+    Command::cargo_bin(PROG)?
+        .args(["--join", FOX])
+        .assert()
+        .code(2)
+        .stderr("--join needs a manifest ending in \"manifest\"\n");
+    Ok(())
+}
+
+#[test]
+fn bad_split_size() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--split-size", "10X", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal split size -- 10X"));
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use crate::{AppResult, Error};
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+
+/// A reader whose errors name the file being read. They convert to
+/// `Error::Input` rather than `Error::Io`, so that a failed read of one file
+/// can be reported and skipped while a failed write still ends the run.
+#[derive(Debug)]
+pub struct Named<R> {
+    path: String,
+    inner: R,
+}
+
+impl<R> Named<R> {
+    pub fn new(path: &str, inner: R) -> Self {
+        Self {
+            path: path.to_string(),
+            inner,
+        }
+    }
+}
+
+impl<R: Read> Read for Named<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let path = &self.path;
+        self.inner.read(buf).map_err(|e| input_error(path, e))
+    }
+}
+
+impl<R: BufRead> BufRead for Named<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let path = &self.path;
+        self.inner.fill_buf().map_err(|e| input_error(path, e))
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+/// The payload of an `io::Error` from a `Named` reader
+#[derive(Debug)]
+pub(crate) struct InputError {
+    pub(crate) path: String,
+    pub(crate) source: io::Error,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl error::Error for InputError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+fn input_error(path: &str, source: io::Error) -> io::Error {
+    // Already named by a nested reader
+    if source.get_ref().is_some_and(|e| e.is::<InputError>()) {
+        return source;
+    }
+
+    let kind = source.kind();
+    let error = InputError {
+        path: path.to_string(),
+        source,
+    };
+    io::Error::new(kind, error)
+}
+
+/// Copies `file` at `path` to `out` with `io::copy`, which may use
+/// `copy_file_range`, `sendfile` or `splice` on Linux. As a failed read
+/// cannot then be told apart from a failed write, any error other than a
+/// broken pipe is taken to be an error reading `path`, as in GNU cat.
+pub fn copy<R, W>(path: &str, file: &mut R, out: &mut W) -> AppResult<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    io::copy(file, out).map_err(|source| match source.kind() {
+        io::ErrorKind::BrokenPipe => Error::Io(source),
+        _ => Error::from(input_error(path, source)),
+    })
+}
+
+/// Reports an error reading one input on stderr, returning whether there was
+/// one. Any other error, such as a failed write, is returned.
+pub fn report(result: AppResult<()>) -> AppResult<bool> {
+    match result {
+        Ok(()) => Ok(false),
+        Err(e @ Error::Input { .. }) => {
+            eprintln!("{}", e);
+            Ok(true)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy, Named};
+    use crate::Error;
+    use std::io::{self, BufRead, BufReader, Read};
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("read failed"))
+        }
+    }
+
+    #[test]
+    fn test_named() {
+        let mut file = Named::new("input.txt", BufReader::new(Failing));
+        let err = file.fill_buf().unwrap_err();
+        assert_eq!(err.to_string(), "read failed");
+
+        let err = Error::from(err);
+        assert!(matches!(err, Error::Input { ref path, .. } if path == "input.txt"));
+        assert_eq!(err.to_string(), "input.txt: read failed");
+
+        // The innermost name is kept
+        let mut file = Named::new("outer", Named::new("inner", Failing));
+        let err = Error::from(file.read(&mut [0; 4]).unwrap_err());
+        assert_eq!(err.to_string(), "inner: read failed");
+
+        let mut line = vec![];
+        let mut file = Named::new("ok.txt", &b"one\ntwo"[..]);
+        assert_eq!(file.read_until(b'\n', &mut line).unwrap(), 4);
+        assert_eq!(line, b"one\n");
+    }
+
+    #[test]
+    fn test_copy() {
+        let mut out = vec![];
+        assert_eq!(copy("ok.txt", &mut &b"data"[..], &mut out).unwrap(), 4);
+        assert_eq!(out, b"data");
+
+        let err = copy("input.txt", &mut Failing, &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "input.txt: read failed");
+    }
+}
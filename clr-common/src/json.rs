@@ -0,0 +1,87 @@
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// Writes records either as a single JSON array (`--json`) or as
+/// newline-delimited JSON with one record per line (`--ndjson`)
+#[derive(Debug)]
+pub struct JsonWriter {
+    ndjson: bool,
+    records: usize,
+}
+
+impl JsonWriter {
+    pub fn new(ndjson: bool) -> Self {
+        Self { ndjson, records: 0 }
+    }
+
+    /// Writes one record
+    pub fn write(&mut self, out: &mut impl Write, record: &impl Serialize) -> io::Result<()> {
+        if !self.ndjson {
+            out.write_all(if self.records == 0 { b"[\n" } else { b",\n" })?;
+        }
+        serde_json::to_writer(&mut *out, record)?;
+        if self.ndjson {
+            out.write_all(b"\n")?;
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Closes the array after the last record. Does nothing for `--ndjson`.
+    pub fn finish(self, out: &mut impl Write) -> io::Result<()> {
+        if !self.ndjson {
+            out.write_all(if self.records == 0 { b"[]\n" } else { b"\n]\n" })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonWriter;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Record {
+        name: &'static str,
+        size: u64,
+    }
+
+    fn write(ndjson: bool, records: &[Record]) -> String {
+        let mut out = vec![];
+        let mut writer = JsonWriter::new(ndjson);
+        for record in records {
+            writer.write(&mut out, record).unwrap();
+        }
+        writer.finish(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json() {
+        let records = [
+            Record { name: "a", size: 1 },
+            Record {
+                name: "b\"",
+                size: 2,
+            },
+        ];
+
+        assert_eq!(write(false, &[]), "[]\n");
+        assert_eq!(
+            write(false, &records),
+            "[\n{\"name\":\"a\",\"size\":1},\n{\"name\":\"b\\\"\",\"size\":2}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let records = [Record { name: "a", size: 1 }, Record { name: "b", size: 2 }];
+
+        assert_eq!(write(true, &[]), "");
+        assert_eq!(
+            write(true, &records),
+            "{\"name\":\"a\",\"size\":1}\n{\"name\":\"b\",\"size\":2}\n"
+        );
+    }
+}
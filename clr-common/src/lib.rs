@@ -0,0 +1,253 @@
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, StdoutLock};
+use std::path::Path;
+use std::process;
+
+#[cfg(feature = "decompress")]
+use std::io::Read;
+
+mod input;
+mod record;
+mod size;
+
+use input::InputError;
+pub use input::{copy, report, Named};
+pub use record::{read_record, records, terminator, Records};
+pub use size::{parse_size, SizeError};
+
+#[cfg(feature = "json")]
+mod json;
+
+#[cfg(feature = "json")]
+pub use json::JsonWriter;
+
+pub type AppResult<T> = Result<T, Error>;
+
+/// Buffered, locked standard output shared by all tools
+pub type Output = BufWriter<StdoutLock<'static>>;
+
+/// Exit status of a process killed by SIGPIPE, as reported by the shell
+const BROKEN_PIPE_STATUS: i32 = 128 + 13;
+
+#[derive(Debug)]
+pub enum Error {
+    /// An input file could not be opened
+    Input { path: String, source: io::Error },
+    /// Reading or writing failed
+    Io(io::Error),
+    /// Invalid combination of arguments
+    Usage(String),
+    /// Any other failure
+    Message(String),
+    /// Exit with the given status; any failures have already been reported
+    Exit(i32),
+}
+
+impl Error {
+    /// Whether stdout was closed before all output was written, e.g. by `head`
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Self::Io(e) if e.kind() == io::ErrorKind::BrokenPipe)
+    }
+
+    /// Process exit status for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => 2,
+            Self::Exit(code) => *code,
+            _ if self.is_broken_pipe() => BROKEN_PIPE_STATUS,
+            _ => 1,
+        }
+    }
+
+    /// Reports the error on stderr and exits. A broken pipe is not reported.
+    pub fn exit(&self) -> ! {
+        if !self.is_broken_pipe() && !matches!(self, Self::Exit(_)) {
+            eprintln!("{}", self);
+        }
+        process::exit(self.exit_code())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Input { path, source } => write!(f, "{}: {}", path, source),
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Usage(msg) | Self::Message(msg) => write!(f, "{}", msg),
+            Self::Exit(code) => write!(f, "exit status {}", code),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Input { source, .. } => Some(source),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    /// Errors from a `Named` reader become `Input` errors
+    fn from(e: io::Error) -> Self {
+        if !e.get_ref().is_some_and(|inner| inner.is::<InputError>()) {
+            return Self::Io(e);
+        }
+
+        match e.into_inner().map(|inner| inner.downcast::<InputError>()) {
+            Some(Ok(inner)) => Self::Input {
+                path: inner.path,
+                source: inner.source,
+            },
+            _ => unreachable!("the payload is an InputError"),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(msg: String) -> Self {
+        Self::Message(msg)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(msg: &str) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
+
+/// Opens `filename` for buffered reading, where `-` means standard input. The
+/// name need not be valid UTF-8.
+pub fn open(filename: impl AsRef<Path>) -> AppResult<Box<dyn BufRead>> {
+    let filename = filename.as_ref();
+    if filename == Path::new("-") {
+        return Ok(Box::new(BufReader::new(io::stdin())));
+    }
+
+    match File::open(filename) {
+        Ok(file) => Ok(Box::new(BufReader::new(file))),
+        Err(source) => Err(Error::Input {
+            path: filename.to_string_lossy().into_owned(),
+            source,
+        }),
+    }
+}
+
+/// Opens `filename` like `open`. With `decompress`, gzip, bzip2, xz and zstd
+/// data is detected by its magic bytes and decompressed, while any other data
+/// is read as is.
+#[cfg(feature = "decompress")]
+pub fn open_input(filename: impl AsRef<Path>, decompress: bool) -> AppResult<Box<dyn BufRead>> {
+    let filename = filename.as_ref();
+    let file = open(filename)?;
+    if !decompress {
+        return Ok(file);
+    }
+
+    self::decompress(file).map_err(|source| Error::Input {
+        path: filename.to_string_lossy().into_owned(),
+        source,
+    })
+}
+
+/// Decompresses `file` if it starts with the magic bytes of gzip, bzip2, xz or
+/// zstd data, and otherwise returns it as is
+#[cfg(feature = "decompress")]
+pub fn decompress(mut file: Box<dyn BufRead>) -> io::Result<Box<dyn BufRead>> {
+    let magic = file.fill_buf()?;
+
+    let reader: Box<dyn Read> = if magic.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::bufread::MultiGzDecoder::new(file))
+    } else if magic.starts_with(b"BZh") {
+        Box::new(bzip2::bufread::MultiBzDecoder::new(file))
+    } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Box::new(xz2::bufread::XzDecoder::new_multi_decoder(file))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Box::new(zstd::Decoder::with_buffer(file)?)
+    } else {
+        return Ok(file);
+    };
+
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+/// Locks standard output for the rest of the process and buffers it. Callers
+/// must `flush` before returning so that write errors are not lost.
+pub fn stdout() -> Output {
+    BufWriter::new(io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open, Error};
+    use std::io;
+
+    #[cfg(feature = "decompress")]
+    #[test]
+    fn test_decompress() {
+        use super::decompress;
+        use std::io::{Cursor, Read, Write};
+
+        let text = "The quick brown fox\njumps over the lazy dog.\n";
+        let read = |data: Vec<u8>| {
+            let mut output = String::new();
+            decompress(Box::new(Cursor::new(data)))
+                .unwrap()
+                .read_to_string(&mut output)
+                .unwrap();
+            output
+        };
+
+        let mut gz = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        gz.write_all(text.as_bytes()).unwrap();
+        assert_eq!(read(gz.finish().unwrap()), text);
+
+        let mut bz = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+        bz.write_all(text.as_bytes()).unwrap();
+        assert_eq!(read(bz.finish().unwrap()), text);
+
+        let mut xz = xz2::write::XzEncoder::new(vec![], 6);
+        xz.write_all(text.as_bytes()).unwrap();
+        assert_eq!(read(xz.finish().unwrap()), text);
+
+        let zst = zstd::encode_all(text.as_bytes(), 0).unwrap();
+        assert_eq!(read(zst), text);
+
+        assert_eq!(read(text.as_bytes().to_vec()), text);
+        assert_eq!(read(vec![]), "");
+    }
+
+    #[test]
+    fn test_open() {
+        assert!(open("Cargo.toml").is_ok());
+
+        let err = open("does-not-exist").err().unwrap();
+        assert!(matches!(err, Error::Input { .. }));
+        assert_eq!(err.exit_code(), 1);
+        assert!(err
+            .to_string()
+            .starts_with("does-not-exist: No such file or directory"));
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(Error::from("failed").exit_code(), 1);
+        assert_eq!(Error::from(io::Error::other("failed")).exit_code(), 1);
+        assert_eq!(Error::Usage("bad arguments".into()).exit_code(), 2);
+        assert_eq!(Error::Exit(3).exit_code(), 3);
+    }
+
+    #[test]
+    fn test_broken_pipe() {
+        let err = Error::from(io::Error::from(io::ErrorKind::BrokenPipe));
+        assert!(err.is_broken_pipe());
+        assert_eq!(err.exit_code(), 141);
+
+        let err = Error::from(io::Error::from(io::ErrorKind::NotFound));
+        assert!(!err.is_broken_pipe());
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{self, BufRead};
+use std::mem;
+
+/// Returns the record terminator: NUL with `-z`/`--null-data`, otherwise a
+/// newline
+pub fn terminator(null_data: bool) -> u8 {
+    if null_data {
+        b'\0'
+    } else {
+        b'\n'
+    }
+}
+
+/// Appends the next record, including its terminator if any, to `buf`. Works
+/// like `BufRead::read_line` but splits on `terminator` instead of `\n`.
+/// Returns the number of bytes read, which is 0 at the end of input.
+pub fn read_record<R: BufRead + ?Sized>(
+    file: &mut R,
+    terminator: u8,
+    buf: &mut String,
+) -> io::Result<usize> {
+    let mut bytes = mem::take(buf).into_bytes();
+    let len = bytes.len();
+    let result = file.read_until(terminator, &mut bytes);
+
+    match String::from_utf8(bytes) {
+        Ok(record) => {
+            *buf = record;
+            result
+        }
+        Err(e) => {
+            let mut bytes = e.into_bytes();
+            bytes.truncate(len);
+            *buf = String::from_utf8(bytes).expect("buffer was valid UTF-8");
+            result.and(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )))
+        }
+    }
+}
+
+/// Iterator over the records of `file` without their terminators, like
+/// `BufRead::lines`. A `\r` before a newline terminator is removed as well.
+pub fn records<R: BufRead>(file: R, terminator: u8) -> Records<R> {
+    Records { file, terminator }
+}
+
+#[derive(Debug)]
+pub struct Records<R> {
+    file: R,
+    terminator: u8,
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = String::new();
+        match read_record(&mut self.file, self.terminator, &mut record) {
+            Ok(0) => None,
+            Ok(_) => {
+                if record.ends_with(char::from(self.terminator)) {
+                    record.pop();
+                    if self.terminator == b'\n' && record.ends_with('\r') {
+                        record.pop();
+                    }
+                }
+                Some(Ok(record))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_record, records};
+    use std::io::{self, Cursor};
+
+    #[test]
+    fn test_read_record() {
+        let mut file = Cursor::new("a\nb\0c\n\0d");
+        let mut buf = String::new();
+
+        assert_eq!(read_record(&mut file, b'\0', &mut buf).unwrap(), 4);
+        assert_eq!(buf, "a\nb\0");
+        assert_eq!(read_record(&mut file, b'\0', &mut buf).unwrap(), 3);
+        assert_eq!(buf, "a\nb\0c\n\0");
+        buf.clear();
+        assert_eq!(read_record(&mut file, b'\0', &mut buf).unwrap(), 1);
+        assert_eq!(buf, "d");
+        assert_eq!(read_record(&mut file, b'\0', &mut buf).unwrap(), 0);
+        assert_eq!(buf, "d");
+    }
+
+    #[test]
+    fn test_read_record_invalid_utf8() {
+        let mut file = Cursor::new(b"\xff\0".to_vec());
+        let mut buf = String::from("a");
+
+        let err = read_record(&mut file, b'\0', &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(buf, "a");
+    }
+
+    #[test]
+    fn test_records() {
+        let lines = records(Cursor::new("a\r\nb\0c\n"), b'\n');
+        assert_eq!(lines.collect::<Result<Vec<_>, _>>().unwrap(), ["a", "b\0c"]);
+
+        let lines = records(Cursor::new("a\r\n\0\0b"), b'\0');
+        assert_eq!(
+            lines.collect::<Result<Vec<_>, _>>().unwrap(),
+            ["a\r\n", "", "b"]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+/// Why a count such as `-c 1M` could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeError {
+    /// Not a number with an optional multiplier suffix
+    Invalid,
+    /// Does not fit in the count type
+    TooLarge,
+}
+
+impl SizeError {
+    /// The message for an invalid `what`, such as "byte count", given as `val`
+    pub fn message(&self, what: &str, val: &str) -> String {
+        match self {
+            Self::Invalid => format!("illegal {} -- {}", what, val),
+            Self::TooLarge => format!("{} too large -- {}", what, val),
+        }
+    }
+}
+
+/// Multiplier prefixes in increasing powers, as in GNU coreutils
+const PREFIXES: &str = "KMGTPEZYRQ";
+
+/// Parses a count of digits followed by an optional GNU multiplier suffix:
+/// `b` (512), `K`/`KiB` (1024), `KB` (1000), and likewise for `M`, `G`, `T`,
+/// `P`, `E`, `Z`, `Y`, `R` and `Q`. A lowercase `k` is accepted for `K`.
+pub fn parse_size(val: &str) -> Result<u64, SizeError> {
+    let split = val.find(|c: char| !c.is_ascii_digit()).unwrap_or(val.len());
+    let (digits, suffix) = val.split_at(split);
+    if digits.is_empty() {
+        return Err(SizeError::Invalid);
+    }
+
+    let multiplier = multiplier(suffix).ok_or(SizeError::Invalid)?;
+    let num: u128 = digits.parse().map_err(|_| SizeError::TooLarge)?;
+
+    num.checked_mul(multiplier)
+        .and_then(|n| u64::try_from(n).ok())
+        .ok_or(SizeError::TooLarge)
+}
+
+fn multiplier(suffix: &str) -> Option<u128> {
+    if suffix.is_empty() {
+        return Some(1);
+    }
+    if suffix == "b" {
+        return Some(512);
+    }
+
+    let mut chars = suffix.chars();
+    let prefix = match chars.next()? {
+        'k' => 'K',
+        c => c,
+    };
+    let power = PREFIXES.find(prefix)? as u32 + 1;
+    let base: u128 = match chars.as_str() {
+        "" | "iB" => 1024,
+        "B" => 1000,
+        _ => return None,
+    };
+
+    base.checked_pow(power)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_size, SizeError};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("42"), Ok(42));
+        assert_eq!(parse_size("2b"), Ok(1024));
+        assert_eq!(parse_size("1K"), Ok(1024));
+        assert_eq!(parse_size("1k"), Ok(1024));
+        assert_eq!(parse_size("1KiB"), Ok(1024));
+        assert_eq!(parse_size("1KB"), Ok(1000));
+        assert_eq!(parse_size("3M"), Ok(3 << 20));
+        assert_eq!(parse_size("3MB"), Ok(3_000_000));
+        assert_eq!(parse_size("1GiB"), Ok(1 << 30));
+        assert_eq!(parse_size("1E"), Ok(1 << 60));
+        assert_eq!(parse_size("15E"), Ok(15 << 60));
+        assert_eq!(parse_size("0Q"), Ok(0));
+
+        for bad in [
+            "", "K", "foo", "-1", "+1", "1.5", "1X", "1KiBB", "1iB", "1 K", "1bB",
+        ] {
+            assert_eq!(parse_size(bad), Err(SizeError::Invalid), "{}", bad);
+        }
+        for big in [
+            "16E",
+            "1Z",
+            "1Y",
+            "1Q",
+            "18446744073709551616",
+            "99999999999999999999999999999999999999999",
+        ] {
+            assert_eq!(parse_size(big), Err(SizeError::TooLarge), "{}", big);
+        }
+    }
+
+    #[test]
+    fn test_message() {
+        assert_eq!(
+            SizeError::Invalid.message("byte count", "1X"),
+            "illegal byte count -- 1X"
+        );
+        assert_eq!(
+            SizeError::TooLarge.message("line count", "1Z"),
+            "line count too large -- 1Z"
+        );
+    }
+}
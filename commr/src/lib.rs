@@ -1,12 +1,7 @@
 use clap::Parser;
-use std::{
-    cmp::Ordering,
-    error::Error,
-    fs::File,
-    io::{self, BufRead, BufReader},
-};
-
-type AppResult<T> = Result<T, Box<dyn Error>>;
+use clr_common::{open, records, terminator, AppResult, Error, JsonWriter};
+use serde::Serialize;
+use std::{cmp::Ordering, io::Write};
 
 #[derive(Debug, Parser)]
 #[clap(
@@ -48,6 +43,18 @@ pub struct App {
         takes_value = true
     )]
     delimiter: String,
+
+    /// Print lines as a JSON array of records
+    #[clap(long = "json", takes_value = false, conflicts_with = "ndjson")]
+    json: bool,
+
+    /// Print lines as one JSON record per line
+    #[clap(long = "ndjson", takes_value = false)]
+    ndjson: bool,
+
+    /// Split and write records on NUL instead of newline
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
 }
 
 #[derive(Debug)]
@@ -57,13 +64,23 @@ enum Column<'a> {
     Column3(&'a str),
 }
 
+/// A JSON record of a line and the column it belongs to: 1 for lines only in
+/// FILE1, 2 for lines only in FILE2 and 3 for lines in both
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    column: u8,
+    line: &'a str,
+}
+
 impl App {
     pub fn run(self) -> AppResult<()> {
         let file1 = &self.file1;
         let file2 = &self.file2;
 
         if file1 == "-" && file2 == "-" {
-            return Err(From::from("both input files cannot be STDIN (\"-\")"));
+            return Err(Error::Usage(
+                "both input files cannot be STDIN (\"-\")".to_string(),
+            ));
         }
 
         let case = |line: String| -> String {
@@ -74,7 +91,23 @@ impl App {
             }
         };
 
-        let print = |column: Column| {
+        let terminator = terminator(self.null_data);
+        let mut out = clr_common::stdout();
+        let mut json = (self.json || self.ndjson).then(|| JsonWriter::new(self.ndjson));
+
+        let mut print = |column: Column| -> AppResult<()> {
+            if let Some(json) = &mut json {
+                let (column, line, suppressed) = match column {
+                    Column::Column1(val) => (1, val, self.show_column1),
+                    Column::Column2(val) => (2, val, self.show_column2),
+                    Column::Column3(val) => (3, val, self.show_column3),
+                };
+                if !suppressed {
+                    json.write(&mut out, &Record { column, line })?;
+                }
+                return Ok(());
+            }
+
             let mut columns = vec![];
 
             match column {
@@ -107,59 +140,67 @@ impl App {
             };
 
             if !columns.is_empty() {
-                println!("{}", columns.join(&self.delimiter));
+                write!(out, "{}", columns.join(&self.delimiter))?;
+                out.write_all(&[terminator])?;
             }
+            Ok(())
         };
 
-        let mut lines1 = open(file1)?.lines().filter_map(Result::ok).map(case);
-        let mut lines2 = open(file2)?.lines().filter_map(Result::ok).map(case);
+        // A line that cannot be read, e.g. one that is not valid UTF-8, fails
+        // the run rather than being skipped, which would misplace later lines
+        let lines = |filename: &str| -> AppResult<_> {
+            let path = filename.to_string();
+            Ok(records(open(filename)?, terminator).map(move |line| {
+                line.map(case).map_err(|source| Error::Input {
+                    path: path.clone(),
+                    source,
+                })
+            }))
+        };
+        let mut lines1 = lines(file1)?;
+        let mut lines2 = lines(file2)?;
 
-        let mut line1 = lines1.next();
-        let mut line2 = lines2.next();
+        let mut line1 = lines1.next().transpose()?;
+        let mut line2 = lines2.next().transpose()?;
 
         while line1.is_some() || line2.is_some() {
             match (&line1, &line2) {
                 (Some(val1), Some(val2)) => match val1.cmp(val2) {
                     Ordering::Equal => {
-                        print(Column::Column3(val1));
-                        line1 = lines1.next();
-                        line2 = lines2.next();
+                        print(Column::Column3(val1))?;
+                        line1 = lines1.next().transpose()?;
+                        line2 = lines2.next().transpose()?;
                     }
 
                     Ordering::Less => {
-                        print(Column::Column1(val1));
-                        line1 = lines1.next();
+                        print(Column::Column1(val1))?;
+                        line1 = lines1.next().transpose()?;
                     }
 
                     Ordering::Greater => {
-                        print(Column::Column2(val2));
-                        line2 = lines2.next();
+                        print(Column::Column2(val2))?;
+                        line2 = lines2.next().transpose()?;
                     }
                 },
 
                 (Some(val1), None) => {
-                    print(Column::Column1(val1));
-                    line1 = lines1.next();
+                    print(Column::Column1(val1))?;
+                    line1 = lines1.next().transpose()?;
                 }
 
                 (None, Some(val2)) => {
-                    print(Column::Column2(val2));
-                    line2 = lines2.next();
+                    print(Column::Column2(val2))?;
+                    line2 = lines2.next().transpose()?;
                 }
 
                 _ => (),
             }
         }
 
+        if let Some(json) = json {
+            json.finish(&mut out)?;
+        }
+        out.flush()?;
         Ok(())
     }
 }
-
-fn open(filename: &str) -> AppResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(
-            File::open(filename).map_err(|e| format!("{}: {}", filename, e))?,
-        ))),
-    }
-}
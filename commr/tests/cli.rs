@@ -0,0 +1,92 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use std::error::Error;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+
+const PROG: &str = "commr";
+const FILE1: &str = "tests/inputs/file1.txt";
+const FILE2: &str = "tests/inputs/file2.txt";
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["Cargo.toml", "src/main.rs"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn json() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--json", FILE1, FILE2])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "[\n",
+            "{\"column\":2,\"line\":\"B\"},\n",
+            "{\"column\":1,\"line\":\"a\"},\n",
+            "{\"column\":1,\"line\":\"b\"},\n",
+            "{\"column\":3,\"line\":\"c\"},\n",
+            "{\"column\":2,\"line\":\"d\"}\n",
+            "]\n",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn ndjson_suppressed() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--ndjson", "-i", "-3", FILE1, FILE2])
+        .assert()
+        .success()
+        .stdout("{\"column\":1,\"line\":\"a\"}\n{\"column\":2,\"line\":\"d\"}\n");
+
+    Ok(())
+}
+
+#[test]
+fn null_data() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-", "tests/inputs/null2.txt"])
+        .write_stdin("a\nb\0c\0")
+        .assert()
+        .success()
+        .stdout("\tB\0a\nb\0\t\tc\0\td\0");
+    Ok(())
+}
+
+#[test]
+fn dies_invalid_utf8() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-", FILE1])
+        .write_stdin(&b"a\n\xff\nz\n"[..])
+        .assert()
+        .code(1)
+        .stdout("\t\ta\n")
+        .stderr("-: stream did not contain valid UTF-8\n");
+    Ok(())
+}
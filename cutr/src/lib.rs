@@ -1,16 +1,13 @@
 use clap::{ErrorKind, IntoApp, Parser};
-use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use clr_common::{open_input, records, report, terminator, AppResult, Error, Named};
+use csv::{ReaderBuilder, StringRecord, Terminator, WriterBuilder};
 use regex::Regex;
 use std::{
-    error::Error,
-    fs::File,
-    io::{self, BufRead, BufReader},
+    io::{self, Write},
     num::NonZeroUsize,
     ops::Range,
 };
 
-type AppResult<T> = Result<T, Box<dyn Error>>;
-
 type PositionList = Vec<Range<usize>>;
 
 #[derive(Debug)]
@@ -70,46 +67,83 @@ pub struct App {
         parse(try_from_str = parse_pos)
     )]
     chars: Option<PositionList>,
+
+    /// Decompress gzip, bzip2, xz and zstd input
+    #[clap(short = 'Z', long = "decompress", takes_value = false)]
+    decompress: bool,
+
+    /// Split and write records on NUL instead of newline
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
 }
 
 impl App {
     pub fn run(self) -> AppResult<()> {
         let extract = self.extract();
+        let terminator = terminator(self.null_data);
+        let mut out = clr_common::stdout();
+        let mut failed = false;
 
         for filename in &self.files {
-            match open(filename) {
-                Err(e) => eprintln!("{}: {}", filename, e),
-                Ok(file) => match &extract {
-                    Extract::Fields(pos) => {
-                        let mut reader = ReaderBuilder::new()
-                            .delimiter(self.delimiter)
-                            .has_headers(false)
-                            .from_reader(file);
-
-                        let mut writer = WriterBuilder::new()
-                            .delimiter(self.delimiter)
-                            .from_writer(io::stdout());
-
-                        for record in reader.records() {
-                            writer.write_record(extract_fields(&record?, pos))?;
-                        }
-                    }
+            failed |= report(self.cut(filename, &extract, terminator, &mut out))?;
+        }
 
-                    Extract::Bytes(pos) => {
-                        for line in file.lines() {
-                            println!("{}", extract_bytes(&line?, pos))
-                        }
-                    }
+        out.flush()?;
 
-                    Extract::Chars(pos) => {
-                        for line in file.lines() {
-                            println!("{}", extract_chars(&line?, pos))
-                        }
-                    }
-                },
-            }
+        if failed {
+            return Err(Error::Exit(1));
         }
+        Ok(())
+    }
+
+    /// Prints the selected parts of each line of one file. Errors reading the
+    /// file are `Error::Input` errors.
+    fn cut(
+        &self,
+        filename: &str,
+        extract: &Extract,
+        terminator: u8,
+        out: &mut impl Write,
+    ) -> AppResult<()> {
+        let file = Named::new(filename, open_input(filename, self.decompress)?);
 
+        match extract {
+            Extract::Fields(pos) => {
+                let mut reader = ReaderBuilder::new();
+                reader.delimiter(self.delimiter).has_headers(false);
+                if self.null_data {
+                    reader.terminator(Terminator::Any(terminator));
+                }
+                let mut reader = reader.from_reader(file);
+
+                let mut writer = WriterBuilder::new()
+                    .delimiter(self.delimiter)
+                    .terminator(Terminator::Any(terminator))
+                    .from_writer(out);
+
+                for record in reader.records() {
+                    let record = record.map_err(csv_error)?;
+                    writer
+                        .write_record(extract_fields(&record, pos))
+                        .map_err(csv_error)?;
+                }
+                writer.flush()?;
+            }
+
+            Extract::Bytes(pos) => {
+                for line in records(file, terminator) {
+                    write!(out, "{}", extract_bytes(&line?, pos))?;
+                    out.write_all(&[terminator])?;
+                }
+            }
+
+            Extract::Chars(pos) => {
+                for line in records(file, terminator) {
+                    write!(out, "{}", extract_chars(&line?, pos))?;
+                    out.write_all(&[terminator])?;
+                }
+            }
+        }
         Ok(())
     }
 
@@ -141,6 +175,7 @@ fn parse_delim(input: &str) -> Result<u8, String> {
     }
 }
 
+#[allow(clippy::useless_conversion)]
 fn parse_pos(range: &str) -> Result<PositionList, String> {
     range
         .split(',')
@@ -171,6 +206,7 @@ fn parse_pos(range: &str) -> Result<PositionList, String> {
         .map_err(From::from)
 }
 
+#[allow(clippy::obfuscated_if_else)]
 fn parse_index(input: &str) -> Result<usize, String> {
     input
         .starts_with('+')
@@ -183,10 +219,15 @@ fn parse_index(input: &str) -> Result<usize, String> {
         })
 }
 
-fn open(filename: &str) -> AppResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+/// Converts a csv error, keeping I/O errors such as a broken pipe intact
+fn csv_error(e: csv::Error) -> io::Error {
+    if !e.is_io_error() {
+        return io::Error::other(e);
+    }
+
+    match e.into_kind() {
+        csv::ErrorKind::Io(e) => e,
+        _ => unreachable!(),
     }
 }
 
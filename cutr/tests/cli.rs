@@ -0,0 +1,139 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use rand::{distributions::Alphanumeric, Rng};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+
+const PROG: &str = "cutr";
+const FOX: &str = "tests/inputs/fox.txt";
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_fields() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["-f", "1", "Cargo.toml"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_chars() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["-c", "1", "Cargo.toml"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+fn gen_bad_file() -> String {
+    loop {
+        let filename: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+#[test]
+fn decompress() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-Z", "-d", " ", "-f", "2,4", &format!("{}.gz", FOX)])
+        .assert()
+        .success()
+        .stdout("quick fox\n");
+
+    Ok(())
+}
+
+#[test]
+fn skips_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", bad);
+
+    Command::cargo_bin(PROG)?
+        .args(["-c", "1-3", &bad, FOX])
+        .assert()
+        .code(1)
+        .stdout("The\n")
+        .stderr(predicate::str::is_match(expected)?);
+
+    Ok(())
+}
+
+#[test]
+fn skips_directory() -> TestResult {
+    for (args, expected) in [
+        (["-c", "1-3"], "The\n"),
+        (
+            ["-f", "1"],
+            "The quick brown fox jumps over the lazy dog.\n",
+        ),
+    ] {
+        Command::cargo_bin(PROG)?
+            .args(args)
+            .args(["tests/inputs", FOX])
+            .assert()
+            .code(1)
+            .stdout(expected)
+            .stderr(predicate::str::starts_with("tests/inputs: "));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn null_data_fields() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-d", ",", "-f", "2"])
+        .write_stdin("a,b\0c,d\0")
+        .assert()
+        .success()
+        .stdout("b\0d\0");
+    Ok(())
+}
+
+#[test]
+fn null_data_chars() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--null-data", "-c", "1-2"])
+        .write_stdin("ab\ncd\0ef\0")
+        .assert()
+        .success()
+        .stdout("ab\0ef\0");
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use clap::Parser;
+use clr_common::AppResult;
+use std::io::Write;
+
+#[derive(Parser, Debug)]
+#[clap(
+    version = "0.1.0",
+    author = "akthrms <akt.hrms@gmail.com>",
+    about = "Rust echo"
+)]
+pub struct Config {
+    /// Input text
+    #[clap(value_name = "TEXT", required = true, min_values = 1)]
+    text: Vec<String>,
+
+    /// Do not print newline
+    #[clap(short = 'n', takes_value = false)]
+    omit_newline: bool,
+}
+
+pub fn run(Config { text, omit_newline }: Config) -> AppResult<()> {
+    let mut out = clr_common::stdout();
+    write!(
+        out,
+        "{}{}",
+        text.join(" "),
+        if omit_newline { "" } else { "\n" }
+    )?;
+    out.flush()?;
+    Ok(())
+}
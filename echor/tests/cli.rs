@@ -1,6 +1,8 @@
-use assert_cmd::Command;
+use assert_cmd::{cargo::cargo_bin, Command};
 use predicates::prelude::*;
 use std::fs;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -41,3 +43,19 @@ fn hello1_no_newline() -> TestResult {
 fn hello2_no_newline() -> TestResult {
     run(&["-n", "Hello", "there"], "tests/expected/hello2_n.txt")
 }
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin("echor"))
+        .args(["Hello there"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
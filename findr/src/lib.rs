@@ -1,12 +1,15 @@
 use clap::Parser;
+use clr_common::{terminator, AppResult, Error, JsonWriter};
 use regex::Regex;
-use std::error::Error;
+use serde::Serialize;
+use std::fs::FileType;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
 use std::str::FromStr;
-use walkdir::WalkDir;
+use walkdir::{DirEntry, WalkDir};
 
-type AppResult<T> = Result<T, Box<dyn Error>>;
-
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum EntryType {
     File,
     Dir,
@@ -26,6 +29,30 @@ impl FromStr for EntryType {
     }
 }
 
+impl EntryType {
+    /// Type of a file, or `None` for other types such as sockets
+    fn of(file_type: FileType) -> Option<Self> {
+        if file_type.is_symlink() {
+            Some(Self::Link)
+        } else if file_type.is_dir() {
+            Some(Self::Dir)
+        } else if file_type.is_file() {
+            Some(Self::File)
+        } else {
+            None
+        }
+    }
+}
+
+/// A JSON record of a found entry
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    path: &'a str,
+    #[serde(rename = "type")]
+    entry_type: Option<EntryType>,
+    size: u64,
+}
+
 #[derive(Parser, Debug)]
 #[clap(
     version = "0.1.0",
@@ -57,41 +84,88 @@ pub struct App {
         possible_values = &["f", "d", "l"]
     )]
     entry_types: Vec<EntryType>,
+
+    /// Print entries as a JSON array of records
+    #[clap(long = "json", takes_value = false, conflicts_with = "ndjson")]
+    json: bool,
+
+    /// Print entries as one JSON record per line
+    #[clap(long = "ndjson", takes_value = false)]
+    ndjson: bool,
+
+    /// Terminate each path with NUL instead of newline
+    #[clap(long = "print0", takes_value = false, conflicts_with_all = &["json", "ndjson"])]
+    print0: bool,
 }
 
 impl App {
     pub fn run(self) -> AppResult<()> {
+        let mut out = clr_common::stdout();
+        let mut json = (self.json || self.ndjson).then(|| JsonWriter::new(self.ndjson));
+        let terminator = terminator(self.print0);
+        let mut failed = false;
+
         for path in &self.paths {
-            let entries = WalkDir::new(path)
-                .into_iter()
-                .filter_map(|entry| match entry {
+            for entry in WalkDir::new(path) {
+                match entry {
                     Err(e) => {
                         eprintln!("{}", e);
-                        None
+                        failed = true;
                     }
-                    Ok(entry) => Some(entry),
-                })
-                .filter(|entry| {
-                    self.names.is_empty()
-                        || self
-                            .names
-                            .iter()
-                            .any(|name| name.is_match(&entry.file_name().to_string_lossy()))
-                })
-                .filter(|entry| {
-                    self.entry_types.is_empty()
-                        || self.entry_types.iter().any(|entry_type| match entry_type {
-                            EntryType::File => entry.file_type().is_file(),
-                            EntryType::Dir => entry.file_type().is_dir(),
-                            EntryType::Link => entry.file_type().is_symlink(),
-                        })
-                })
-                .map(|entry| entry.path().display().to_string())
-                .collect::<Vec<_>>();
-
-            println!("{}", entries.join("\n"));
+
+                    Ok(entry) if self.is_match(&entry) => match &mut json {
+                        None => {
+                            out.write_all(entry.path().as_os_str().as_bytes())?;
+                            out.write_all(&[terminator])?;
+                        }
+
+                        Some(json) => match entry.metadata() {
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                failed = true;
+                            }
+
+                            Ok(metadata) => {
+                                let record = Record {
+                                    path: &entry.path().display().to_string(),
+                                    entry_type: EntryType::of(entry.file_type()),
+                                    size: metadata.len(),
+                                };
+                                json.write(&mut out, &record)?;
+                            }
+                        },
+                    },
+
+                    Ok(_) => (),
+                }
+            }
+        }
+
+        if let Some(json) = json {
+            json.finish(&mut out)?;
         }
+        out.flush()?;
 
+        if failed {
+            return Err(Error::Exit(1));
+        }
         Ok(())
     }
+
+    fn is_match(&self, entry: &DirEntry) -> bool {
+        let name_match = self.names.is_empty()
+            || self
+                .names
+                .iter()
+                .any(|name| name.is_match(&entry.file_name().to_string_lossy()));
+
+        let type_match = self.entry_types.is_empty()
+            || self.entry_types.iter().any(|entry_type| match entry_type {
+                EntryType::File => entry.file_type().is_file(),
+                EntryType::Dir => entry.file_type().is_dir(),
+                EntryType::Link => entry.file_type().is_symlink(),
+            });
+
+        name_match && type_match
+    }
 }
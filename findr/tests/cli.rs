@@ -0,0 +1,107 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use rand::{distributions::Alphanumeric, Rng};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+
+const PROG: &str = "findr";
+const FOX: &str = "tests/inputs/fox.txt";
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["src"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+fn gen_bad_file() -> String {
+    loop {
+        let filename: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+#[test]
+fn skips_bad_dir() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", bad);
+
+    Command::cargo_bin(PROG)?
+        .args([&bad, FOX])
+        .assert()
+        .code(1)
+        .stdout(format!("{}\n", FOX))
+        .stderr(predicate::str::is_match(expected)?);
+
+    Ok(())
+}
+
+#[test]
+fn json() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--json", FOX])
+        .assert()
+        .success()
+        .stdout(format!(
+            "[\n{{\"path\":\"{}\",\"type\":\"file\",\"size\":45}}\n]\n",
+            FOX
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn ndjson() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--ndjson", "tests/inputs", "-t", "d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "{\"path\":\"tests/inputs\",\"type\":\"dir\",\"size\":",
+        ))
+        .stdout(predicate::str::contains(FOX).not());
+
+    Ok(())
+}
+
+#[test]
+fn print0() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--print0", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(format!("tests/inputs\0{}\0", FOX));
+    Ok(())
+}
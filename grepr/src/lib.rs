@@ -1,14 +1,19 @@
 use clap::{ErrorKind, IntoApp, Parser};
+use clr_common::{open_input, read_record, terminator, AppResult, Error, JsonWriter};
 use regex::{Regex, RegexBuilder};
+use serde::Serialize;
 use std::{
-    error::Error,
-    fs::{self, File},
-    io::{self, BufRead, BufReader},
+    fs,
+    io::{BufRead, Write},
     mem,
 };
 use walkdir::WalkDir;
 
-type AppResult<T> = Result<T, Box<dyn Error>>;
+/// Exit status when no lines were selected
+const NO_MATCH_STATUS: i32 = 1;
+
+/// Exit status when any error occurred, whether or not lines were selected
+const ERROR_STATUS: i32 = 2;
 
 #[derive(Debug, Parser)]
 #[clap(
@@ -40,44 +45,140 @@ pub struct App {
     /// Invert match
     #[clap(short = 'v', long = "invert-match", takes_value = false)]
     invert_match: bool,
+
+    /// Decompress gzip, bzip2, xz and zstd input
+    #[clap(long = "decompress", takes_value = false)]
+    decompress: bool,
+
+    /// Print matches as a JSON array of records
+    #[clap(long = "json", takes_value = false, conflicts_with = "ndjson")]
+    json: bool,
+
+    /// Print matches as one JSON record per line
+    #[clap(long = "ndjson", takes_value = false)]
+    ndjson: bool,
+
+    /// Split and write records on NUL instead of newline
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
+}
+
+/// A selected line
+#[derive(Debug)]
+struct Line {
+    /// Line number, starting at 1
+    number: usize,
+    /// Byte offset of the start of the line in the file
+    offset: u64,
+    text: String,
+}
+
+/// A JSON record of a selected line
+#[derive(Debug, Serialize)]
+struct LineRecord<'a> {
+    file: &'a str,
+    line_number: usize,
+    byte_offset: u64,
+    line: &'a str,
+}
+
+/// A JSON record of the number of selected lines in a file (`--count`)
+#[derive(Debug, Serialize)]
+struct CountRecord<'a> {
+    file: &'a str,
+    count: usize,
 }
 
 impl App {
     pub fn run(self) -> AppResult<()> {
+        let (matched, failed) = self.search().map_err(|e| {
+            if e.is_broken_pipe() {
+                e
+            } else {
+                eprintln!("{}", e);
+                Error::Exit(ERROR_STATUS)
+            }
+        })?;
+
+        if failed {
+            Err(Error::Exit(ERROR_STATUS))
+        } else if !matched {
+            Err(Error::Exit(NO_MATCH_STATUS))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Prints the selected lines and returns whether any line was selected
+    /// and whether any file failed
+    fn search(&self) -> AppResult<(bool, bool)> {
         let pattern = self.build_pattern()?;
+        let terminator = terminator(self.null_data);
         let entries = find_files(&self.files, self.recursive);
         let entries_len = entries.len();
 
-        let print = |filename: &str, val: &str| {
+        let mut out = clr_common::stdout();
+        let mut json = (self.json || self.ndjson).then(|| JsonWriter::new(self.ndjson));
+        let mut matched = false;
+        let mut failed = false;
+
+        let print = |out: &mut clr_common::Output, filename: &str, val: &str| -> AppResult<()> {
             if entries_len > 1 {
-                print!("{}:{}", filename, val);
+                write!(out, "{}:{}", filename, val)?;
             } else {
-                print!("{}", val);
+                write!(out, "{}", val)?;
             }
+            Ok(())
         };
 
         for entry in entries {
             match entry {
                 Err(e) => {
-                    eprintln!("{}", e)
+                    eprintln!("{}", e);
+                    failed = true;
                 }
 
-                Ok(filename) => match open(&filename) {
+                Ok(filename) => match open_input(&filename, self.decompress) {
                     Err(e) => {
-                        eprintln!("{}: {}", filename, e)
+                        eprintln!("{}", e);
+                        failed = true;
                     }
 
-                    Ok(file) => match find_lines(file, &pattern, self.invert_match) {
+                    Ok(file) => match find_lines(file, &pattern, self.invert_match, terminator) {
                         Err(e) => {
-                            eprintln!("{}", e)
+                            eprintln!("{}: {}", filename, e);
+                            failed = true;
                         }
 
                         Ok(matches) => {
-                            if self.count {
-                                print(&filename, &format!("{}\n", matches.len()));
+                            matched |= !matches.is_empty();
+
+                            if let Some(json) = &mut json {
+                                if self.count {
+                                    let record = CountRecord {
+                                        file: &filename,
+                                        count: matches.len(),
+                                    };
+                                    json.write(&mut out, &record)?;
+                                } else {
+                                    for line in &matches {
+                                        let record = LineRecord {
+                                            file: &filename,
+                                            line_number: line.number,
+                                            byte_offset: line.offset,
+                                            line: line
+                                                .text
+                                                .trim_end_matches(char::from(terminator))
+                                                .trim_end_matches('\r'),
+                                        };
+                                        json.write(&mut out, &record)?;
+                                    }
+                                }
+                            } else if self.count {
+                                print(&mut out, &filename, &format!("{}\n", matches.len()))?;
                             } else {
                                 for line in &matches {
-                                    print(&filename, line);
+                                    print(&mut out, &filename, &line.text)?;
                                 }
                             }
                         }
@@ -86,7 +187,11 @@ impl App {
             }
         }
 
-        Ok(())
+        if let Some(json) = json {
+            json.finish(&mut out)?;
+        }
+        out.flush()?;
+        Ok((matched, failed))
     }
 
     fn build_pattern(&self) -> AppResult<Regex> {
@@ -104,33 +209,36 @@ impl App {
     }
 }
 
-fn open(filename: &str) -> AppResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
-}
-
 fn find_lines<T: BufRead>(
     mut file: T,
     pattern: &Regex,
     invert_match: bool,
-) -> AppResult<Vec<String>> {
+    terminator: u8,
+) -> AppResult<Vec<Line>> {
     let mut matches = vec![];
 
     let mut line = String::new();
+    let mut number = 0;
+    let mut offset = 0;
 
     loop {
-        let bytes = file.read_line(&mut line)?;
+        let bytes = read_record(&mut file, terminator, &mut line)?;
 
         if bytes == 0 {
             break;
         }
 
+        number += 1;
+
         if pattern.is_match(&line) ^ invert_match {
-            matches.push(mem::take(&mut line));
+            matches.push(Line {
+                number,
+                offset,
+                text: mem::take(&mut line),
+            });
         }
 
+        offset += bytes as u64;
         line.clear();
     }
 
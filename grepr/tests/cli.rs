@@ -0,0 +1,201 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use rand::{distributions::Alphanumeric, Rng};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+
+const PROG: &str = "grepr";
+const FOX: &str = "tests/inputs/fox.txt";
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["name", "Cargo.toml"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+fn gen_bad_file() -> String {
+    loop {
+        let filename: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+#[test]
+fn exit_match() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["fox", FOX])
+        .assert()
+        .code(0)
+        .stdout(fs::read_to_string(FOX)?);
+
+    Ok(())
+}
+
+#[test]
+fn exit_no_match() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["cat", FOX])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("");
+
+    Ok(())
+}
+
+#[test]
+fn exit_count_no_match() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-c", "cat", FOX])
+        .assert()
+        .code(1)
+        .stdout("0\n");
+
+    Ok(())
+}
+
+#[test]
+fn exit_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", bad);
+
+    Command::cargo_bin(PROG)?
+        .args(["fox", &bad, FOX])
+        .assert()
+        .code(2)
+        .stdout(format!("{}:{}", FOX, fs::read_to_string(FOX)?))
+        .stderr(predicate::str::is_match(expected)?);
+
+    Ok(())
+}
+
+#[test]
+fn decompress() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--decompress", "fox", &format!("{}.gz", FOX)])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string(FOX)?);
+
+    Ok(())
+}
+
+#[test]
+fn decompress_recursive() -> TestResult {
+    let expected = fs::read_to_string(FOX)?;
+    Command::cargo_bin(PROG)?
+        .args(["-r", "--decompress", "fox", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{}:{}", FOX, expected)))
+        .stdout(predicate::str::contains(format!("{}.gz:{}", FOX, expected)));
+
+    Ok(())
+}
+
+#[test]
+fn json() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--json", "dog", FOX])
+        .assert()
+        .success()
+        .stdout(format!(
+            "[\n{{\"file\":\"{}\",\"line_number\":1,\"byte_offset\":0,\"line\":\"{}\"}}\n]\n",
+            FOX,
+            fs::read_to_string(FOX)?.trim_end()
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn json_no_match() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--json", "cat", FOX])
+        .assert()
+        .code(1)
+        .stdout("[]\n");
+
+    Ok(())
+}
+
+#[test]
+fn ndjson() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--ndjson", "-v", "o"])
+        .write_stdin("one\ntwo\nthree\nfour\n")
+        .assert()
+        .success()
+        .stdout("{\"file\":\"-\",\"line_number\":3,\"byte_offset\":8,\"line\":\"three\"}\n");
+
+    Ok(())
+}
+
+#[test]
+fn ndjson_count() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--ndjson", "-c", "fox", FOX, FOX])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{{\"file\":\"{0}\",\"count\":1}}\n{{\"file\":\"{0}\",\"count\":1}}\n",
+            FOX
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn exit_directory() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["fox", "tests/inputs"])
+        .assert()
+        .code(2)
+        .stderr("tests/inputs is a directory\n");
+
+    Ok(())
+}
+
+#[test]
+fn null_data() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "fox"])
+        .write_stdin("the\nfox\0cat\0fox\0")
+        .assert()
+        .success()
+        .stdout("the\nfox\0fox\0");
+    Ok(())
+}
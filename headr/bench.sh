@@ -0,0 +1,39 @@
+#! /bin/bash
+
+# Compares reading the first line of many small CSV files with and without
+# --jobs, on tmpfs so that the files are in memory.
+# Usage: ./bench.sh [FILES] [JOBS] (default 20000 files, one job per CPU)
+
+set -eu
+
+FILES="${1:-20000}"
+JOBS="${2:-$(nproc)}"
+TMP_DIR=/dev/shm
+[[ -d "$TMP_DIR" ]] || TMP_DIR="${TMPDIR:-/tmp}"
+WORK_DIR=$(mktemp -d "${TMP_DIR}/headr-bench.XXXXXX")
+trap 'rm -rf "$WORK_DIR"' EXIT
+
+cargo build --release --quiet
+HEADR="$(pwd)/target/release/headr"
+
+echo "Creating ${FILES} CSV files in ${WORK_DIR}"
+for ((i = 0; i < FILES; i++)); do
+    DIR="${WORK_DIR}/data/$((i % 100))"
+    [[ -d "$DIR" ]] || mkdir -p "$DIR"
+    printf 'id,name,value\n%d,row,%d\n' "$i" "$((i * 7))" > "${DIR}/${i}.csv"
+done
+
+# Prints the wall-clock time of a command on stderr
+run() {
+    TIMEFORMAT="$(printf "%-28s" "$1") %R s"
+    shift
+    time "$@"
+}
+
+cd "$WORK_DIR"
+mapfile -t CSV_FILES < <(find data -name '*.csv' | sort)
+run "head -n 1" head -n 1 "${CSV_FILES[@]}" > head.out
+run "headr -n 1" "$HEADR" -n 1 "${CSV_FILES[@]}" > sequential.out
+run "headr -n 1 --jobs ${JOBS}" "$HEADR" -n 1 --jobs "$JOBS" "${CSV_FILES[@]}" > parallel.out
+cmp head.out sequential.out
+cmp sequential.out parallel.out
@@ -13,6 +13,8 @@ for FILE in $IN_DIR/*.txt; do
     head -c 1 $FILE > ${OUT_DIR}/${BASENAME}.c1.out
     head -c 2 $FILE > ${OUT_DIR}/${BASENAME}.c2.out
     head -c 4 $FILE > ${OUT_DIR}/${BASENAME}.c4.out
+    head -n -2 $FILE > ${OUT_DIR}/${BASENAME}.n-2.out
+    head -c -4 $FILE > ${OUT_DIR}/${BASENAME}.c-4.out
 done
 
 ALL="$IN_DIR/empty.txt $IN_DIR/one.txt $IN_DIR/two.txt $IN_DIR/three.txt $IN_DIR/ten.txt"
@@ -22,3 +24,15 @@ head -n 4 $ALL > ${OUT_DIR}/all.n4.out
 head -c 1 $ALL > ${OUT_DIR}/all.c1.out
 head -c 2 $ALL > ${OUT_DIR}/all.c2.out
 head -c 4 $ALL > ${OUT_DIR}/all.c4.out
+head -n -2 $ALL > ${OUT_DIR}/all.n-2.out
+head -c -4 $ALL > ${OUT_DIR}/all.c-4.out
+head -q $ALL > ${OUT_DIR}/all.q.out
+head -v $IN_DIR/one.txt > ${OUT_DIR}/one.txt.v.out
+
+# Lines ending in \r\n, with an embedded \n, and in \r, without a final terminator
+CRLF="$IN_DIR/windows.crlf"
+CR="$IN_DIR/mac.cr"
+awk 'BEGIN { RS = "\r\n"; ORS = "\r\n" } NR <= 3' $CRLF > ${OUT_DIR}/windows.crlf.n3.out
+awk 'BEGIN { RS = "\r\n"; ORS = "\r\n" } NR > 1 { print prev } { prev = $0 }' $CRLF > ${OUT_DIR}/windows.crlf.n-1.out
+tr '\r' '\n' < $CR | head -n 2 | tr '\n' '\r' > ${OUT_DIR}/mac.cr.n2.out
+tr '\r' '\n' < $CR | head -n -1 | tr '\n' '\r' > ${OUT_DIR}/mac.cr.n-1.out
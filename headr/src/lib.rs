@@ -1,9 +1,51 @@
+mod lines;
+
 use clap::Parser;
-use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use clr_common::{open_input, parse_size, report, terminator, AppResult, Error, Named, SizeError};
+use lines::{LineEnding, Lines};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::mem;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Mutex;
+use std::thread;
+
+const BUFFER_SIZE: usize = 8192;
+
+/// With --jobs, how many files a thread reads at a time, so that small files
+/// are not dominated by handing them out
+const FILES_PER_JOB: usize = 32;
 
-type AppResult<T> = Result<T, Box<dyn Error>>;
+/// With --jobs, the most output of one file passed to the printing thread at
+/// a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// With --jobs, how many batches of files per thread may be read ahead of the
+/// file being printed
+const BATCHES_PER_JOB: usize = 4;
+
+/// How many lines or bytes of each file to print
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Count {
+    /// The first N
+    First(usize),
+    /// All but the last N, given as -N
+    AllButLast(usize),
+}
+
+impl FromStr for Count {
+    type Err = SizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('-') {
+            // As in GNU head, -0 means all of the input
+            Some(n) => parse_int(n).map(Count::AllButLast),
+            None => parse_positive_int(s).map(Count::First),
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -16,87 +58,437 @@ pub struct App {
     #[clap(value_name = "FILE", multiple_occurrences = true, default_value = "-")]
     files: Vec<String>,
 
-    /// Number of lines
+    /// Number of lines, with an optional multiplier suffix such as K or MiB;
+    /// with a leading '-', all but the last LINES lines
     #[clap(
         short = 'n',
         long = "lines",
         value_name = "LINES",
-        default_value_t = 10,
+        default_value = "10",
+        allow_hyphen_values = true,
         parse(try_from_str = parse_lines)
     )]
-    lines: usize,
+    lines: Count,
 
-    /// Number of bytes
+    /// Number of bytes, with an optional multiplier suffix such as K or MiB;
+    /// with a leading '-', all but the last BYTES bytes
     #[clap(
         short = 'c',
         long = "bytes",
         value_name = "BYTES",
         takes_value = true,
+        allow_hyphen_values = true,
         conflicts_with = "lines",
         parse(try_from_str = parse_bytes)
     )]
-    bytes: Option<usize>,
+    bytes: Option<Count>,
+
+    /// Number of characters, counted as Unicode scalar values
+    #[clap(
+        long = "chars",
+        value_name = "CHARS",
+        takes_value = true,
+        conflicts_with_all = &["lines", "bytes"],
+        parse(try_from_str = parse_chars)
+    )]
+    chars: Option<usize>,
+
+    /// Never print headers giving file names
+    #[clap(
+        short = 'q',
+        long = "quiet",
+        visible_alias = "silent",
+        takes_value = false,
+        conflicts_with = "verbose"
+    )]
+    quiet: bool,
+
+    /// Always print headers giving file names
+    #[clap(short = 'v', long = "verbose", takes_value = false)]
+    verbose: bool,
+
+    /// Header template, where {file} is replaced by the file name and {size}
+    /// by its size in bytes (? if unknown). Unlike the default header, no
+    /// blank line is printed between files.
+    #[clap(
+        long = "header-format",
+        value_name = "TEMPLATE",
+        allow_hyphen_values = true
+    )]
+    header_format: Option<String>,
+
+    /// Decompress gzip, bzip2, xz and zstd input
+    #[clap(short = 'Z', long = "decompress", takes_value = false)]
+    decompress: bool,
+
+    /// Split and write records on NUL instead of newline
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
+
+    /// Line terminator: \n (lf), \r\n (crlf), \r (cr) or whichever ends the
+    /// first line of each file (auto). Terminators are printed as they are.
+    #[clap(
+        long = "line-ending",
+        value_name = "ENDING",
+        default_value = "lf",
+        possible_values = &["lf", "crlf", "cr", "auto"],
+        conflicts_with = "null-data"
+    )]
+    line_ending: LineEnding,
+
+    /// Read up to JOBS files at a time, still printing them in order
+    #[clap(
+        short = 'j',
+        long = "jobs",
+        value_name = "JOBS",
+        default_value_t = 1,
+        parse(try_from_str = parse_jobs)
+    )]
+    jobs: usize,
 }
 
 impl App {
     pub fn run(self) -> AppResult<()> {
+        let mut out = clr_common::stdout();
+        let failed = match self.jobs {
+            1 => self.head_files(&mut out)?,
+            jobs => self.head_files_parallel(jobs, &mut out)?,
+        };
+
+        out.flush()?;
+
+        if failed {
+            return Err(Error::Exit(1));
+        }
+        Ok(())
+    }
+
+    /// Prints the head of each file in turn. Returns whether any file could
+    /// not be read.
+    fn head_files(&self, out: &mut impl Write) -> AppResult<bool> {
+        let mut failed = false;
         for (file_num, filename) in self.files.iter().enumerate() {
-            match open(&filename) {
-                Err(e) => eprintln!("{}: {}", filename, e),
-                Ok(mut file) => {
-                    if self.files.len() > 1 {
-                        println!(
-                            "{}==> {} <==",
-                            if file_num > 0 { "\n" } else { "" },
-                            filename
-                        );
-                    }
+            failed |= report(self.head(filename, file_num, out))?;
+        }
+        Ok(failed)
+    }
 
-                    if let Some(bytes) = self.bytes {
-                        let mut handle = file.take(bytes as u64);
-                        let mut buffer = vec![0; bytes];
-                        let bytes = handle.read(&mut buffer)?;
-                        print!("{}", String::from_utf8_lossy(&buffer[..bytes]));
-                    } else {
-                        let mut line = String::new();
-                        for _ in 0..self.lines {
-                            let bytes = file.read_line(&mut line)?;
-                            if bytes == 0 {
+    /// Reads the heads of files on `jobs` threads, `FILES_PER_JOB` files at a
+    /// time, and prints them in the order of the arguments. The output of a
+    /// file is passed to the printing thread in chunks of at most
+    /// `CHUNK_SIZE` bytes, and a thread waits while its batch has
+    /// `FILES_PER_JOB` chunks unprinted, so large heads are streamed rather
+    /// than held in memory. Returns whether any file could not be read.
+    fn head_files_parallel(&self, jobs: usize, out: &mut impl Write) -> AppResult<bool> {
+        // Every batch that may be pending fits, so that queueing a batch
+        // never waits for a thread that waits for its output to be printed
+        let (job_tx, job_rx) =
+            mpsc::sync_channel::<(usize, SyncSender<Chunk>)>(jobs * BATCHES_PER_JOB + 1);
+        let job_rx = Mutex::new(job_rx);
+        let next_job = || job_rx.lock().ok()?.recv().ok();
+
+        thread::scope(|scope| {
+            // Owned here so that returning early also stops the workers
+            let job_tx = job_tx;
+
+            for _ in 0..jobs {
+                scope.spawn(|| {
+                    while let Some((start, batch_tx)) = next_job() {
+                        let end = self.files.len().min(start + FILES_PER_JOB);
+                        for file_num in start..end {
+                            let mut writer = ChunkWriter::new(&batch_tx);
+                            let result = self.head(&self.files[file_num], file_num, &mut writer);
+                            // The receiver is gone if printing has already failed
+                            if writer.finish(result).is_err() {
                                 break;
                             }
-                            print!("{}", line);
-                            line.clear();
                         }
                     }
+                });
+            }
+
+            // Bounds the output held for files that are done while an
+            // earlier file is still being read
+            let mut pending = VecDeque::new();
+            let mut print_next =
+                |pending: &mut VecDeque<(Receiver<Chunk>, usize)>| -> AppResult<bool> {
+                    let (batch_rx, mut files) = pending.pop_front().expect("a batch is pending");
+                    let mut failed = false;
+
+                    while files > 0 {
+                        let chunk = batch_rx
+                            .recv()
+                            .map_err(|_| Error::from("a worker thread failed"))?;
+                        out.write_all(&chunk.data)?;
+
+                        // The output of a file that fails is kept up to the
+                        // error, as it is when reading the files in turn
+                        if let Some(result) = chunk.end {
+                            failed |= report(result)?;
+                            files -= 1;
+                        }
+                    }
+                    Ok(failed)
+                };
+
+            let mut failed = false;
+            for start in (0..self.files.len()).step_by(FILES_PER_JOB) {
+                let (batch_tx, batch_rx) = mpsc::sync_channel(FILES_PER_JOB);
+                if job_tx.send((start, batch_tx)).is_err() {
+                    return Err("a worker thread failed".into());
+                }
+                let files = self.files.len().min(start + FILES_PER_JOB) - start;
+                pending.push_back((batch_rx, files));
+
+                if pending.len() > jobs * BATCHES_PER_JOB {
+                    failed |= print_next(&mut pending)?;
+                }
+            }
+            drop(job_tx);
+
+            while !pending.is_empty() {
+                failed |= print_next(&mut pending)?;
+            }
+            Ok(failed)
+        })
+    }
+
+    /// Prints the header, if any, and the head of one file. Errors reading the
+    /// file are `Error::Input` errors, after any output up to the error.
+    fn head(&self, filename: &str, file_num: usize, out: &mut impl Write) -> AppResult<()> {
+        let file = Named::new(filename, open_input(filename, self.decompress)?);
+        let terminator = terminator(self.null_data);
+
+        if !self.quiet && (self.verbose || self.files.len() > 1) {
+            writeln!(out, "{}", self.header(filename, file_num))?;
+        }
+
+        match (self.chars, self.bytes, self.lines) {
+            (Some(chars), _, _) => first_chars(file, chars, out)?,
+            (None, Some(Count::First(bytes)), _) => {
+                io::copy(&mut file.take(bytes as u64), out)?;
+            }
+            (None, Some(Count::AllButLast(bytes)), _) => all_but_last_bytes(file, bytes, out)?,
+            (None, None, Count::First(count)) => {
+                let mut lines = Lines::new(file, self.line_ending, terminator);
+                let mut line = vec![];
+                for _ in 0..count {
+                    if lines.read_line(&mut line)? == 0 {
+                        break;
+                    }
+                    out.write_all(&line)?;
+                    line.clear();
                 }
             }
+            (None, None, Count::AllButLast(count)) => {
+                let lines = Lines::new(file, self.line_ending, terminator);
+                all_but_last_lines(lines, count, out)?
+            }
         }
+
         Ok(())
     }
+
+    /// The header printed before the output of the `file_num`th file
+    fn header(&self, filename: &str, file_num: usize) -> String {
+        match &self.header_format {
+            Some(format) => format
+                .replace("{size}", &file_size(filename))
+                .replace("{file}", filename),
+            None => format!(
+                "{}==> {} <==",
+                if file_num > 0 { "\n" } else { "" },
+                filename
+            ),
+        }
+    }
+}
+
+/// Part of the output of one file read by --jobs
+struct Chunk {
+    data: Vec<u8>,
+    /// Set on the last chunk of a file, to the result of reading it
+    end: Option<AppResult<()>>,
 }
 
-fn open(filename: &str) -> AppResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+/// Sends what is written to the printing thread in chunks of at most
+/// `CHUNK_SIZE` bytes
+struct ChunkWriter<'a> {
+    tx: &'a SyncSender<Chunk>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> ChunkWriter<'a> {
+    fn new(tx: &'a SyncSender<Chunk>) -> Self {
+        Self { tx, buffer: vec![] }
+    }
+
+    /// Sends the rest of the output with the `result` of reading the file
+    fn finish(self, result: AppResult<()>) -> io::Result<()> {
+        let chunk = Chunk {
+            data: self.buffer,
+            end: Some(result),
+        };
+        self.tx.send(chunk).map_err(|_| closed())
+    }
+}
+
+impl Write for ChunkWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buffer.len() == CHUNK_SIZE {
+            let chunk = Chunk {
+                data: mem::take(&mut self.buffer),
+                end: None,
+            };
+            self.tx.send(chunk).map_err(|_| closed())?;
+        }
+
+        let len = buf.len().min(CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The printing thread is gone, as printing has failed
+fn closed() -> io::Error {
+    io::Error::from(io::ErrorKind::BrokenPipe)
+}
+
+/// The size of a regular file in bytes, or ? for anything else such as a pipe
+fn file_size(filename: &str) -> String {
+    let path = match filename {
+        "-" => "/dev/stdin",
+        _ => filename,
+    };
+
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => metadata.len().to_string(),
+        _ => "?".to_string(),
     }
 }
 
-fn parse_lines(val: &str) -> Result<usize, String> {
-    parse_positive_int(val).map_err(|e| format!("illegal line count -- {}", e))
+/// Prints all but the last `count` lines, holding back at most `count` lines
+/// at a time
+fn all_but_last_lines(
+    mut lines: Lines<impl BufRead>,
+    count: usize,
+    out: &mut impl Write,
+) -> AppResult<()> {
+    let mut held: VecDeque<Vec<u8>> = VecDeque::new();
+    let mut line = vec![];
+
+    while lines.read_line(&mut line)? > 0 {
+        held.push_back(line);
+        line = match held.len() > count {
+            true => {
+                let mut first = held.pop_front().unwrap_or_default();
+                out.write_all(&first)?;
+                first.clear();
+                first
+            }
+            false => vec![],
+        };
+    }
+
+    Ok(())
 }
 
-fn parse_bytes(val: &str) -> Result<usize, String> {
-    parse_positive_int(val).map_err(|e| format!("illegal byte count -- {}", e))
+/// Prints all but the last `count` bytes of `file`, holding back at most
+/// `count` bytes at a time
+fn all_but_last_bytes(mut file: impl Read, count: usize, out: &mut impl Write) -> AppResult<()> {
+    let mut held: VecDeque<u8> = VecDeque::new();
+    let mut buffer = [0; BUFFER_SIZE];
+
+    loop {
+        let bytes = file.read(&mut buffer)?;
+        if bytes == 0 {
+            break;
+        }
+        held.extend(&buffer[..bytes]);
+
+        let excess = held.len().saturating_sub(count);
+        let (front, back) = held.as_slices();
+        let split = excess.min(front.len());
+        out.write_all(&front[..split])?;
+        out.write_all(&back[..excess - split])?;
+        held.drain(..excess);
+    }
+
+    Ok(())
 }
 
-fn parse_positive_int(val: &str) -> Result<usize, String> {
+/// Prints the first `count` characters of `file` as they are. A character
+/// starts at every byte that is not a UTF-8 continuation byte, so a multi-byte
+/// character is never cut and invalid bytes are passed through unchanged.
+fn first_chars(mut file: impl BufRead, count: usize, out: &mut impl Write) -> AppResult<()> {
+    let mut chars = 0;
+
+    loop {
+        let buffer = file.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let end = buffer.iter().position(|&byte| {
+            if !is_continuation_byte(byte) {
+                chars += 1;
+            }
+            chars > count
+        });
+        match end {
+            Some(end) => {
+                out.write_all(&buffer[..end])?;
+                return Ok(());
+            }
+            None => {
+                let len = buffer.len();
+                out.write_all(buffer)?;
+                file.consume(len);
+            }
+        }
+    }
+}
+
+fn is_continuation_byte(byte: u8) -> bool {
+    byte & 0xc0 == 0x80
+}
+
+fn parse_lines(val: &str) -> Result<Count, String> {
+    val.parse()
+        .map_err(|e: SizeError| e.message("line count", val))
+}
+
+fn parse_bytes(val: &str) -> Result<Count, String> {
+    val.parse()
+        .map_err(|e: SizeError| e.message("byte count", val))
+}
+
+fn parse_chars(val: &str) -> Result<usize, String> {
+    parse_positive_int(val).map_err(|e| e.message("character count", val))
+}
+
+fn parse_jobs(val: &str) -> Result<usize, String> {
     match val.parse() {
         Ok(n) if n > 0 => Ok(n),
-        _ => Err(val.into()),
+        _ => Err(format!("illegal number of jobs -- {}", val)),
+    }
+}
+
+fn parse_positive_int(val: &str) -> Result<usize, SizeError> {
+    match parse_int(val)? {
+        0 => Err(SizeError::Invalid),
+        n => Ok(n),
     }
 }
 
+fn parse_int(val: &str) -> Result<usize, SizeError> {
+    parse_size(val)?.try_into().map_err(|_| SizeError::TooLarge)
+}
+
 #[test]
 fn test_parse_positive_int() {
     let res = parse_positive_int("3");
@@ -105,9 +497,79 @@ fn test_parse_positive_int() {
 
     let res = parse_positive_int("foo");
     assert!(res.is_err());
-    assert_eq!(res.unwrap_err().to_string(), "foo");
+    assert_eq!(res.unwrap_err(), SizeError::Invalid);
 
     let res = parse_positive_int("0");
     assert!(res.is_err());
-    assert_eq!(res.unwrap_err().to_string(), "0");
+    assert_eq!(res.unwrap_err(), SizeError::Invalid);
+
+    assert_eq!(parse_positive_int("2K"), Ok(2048));
+    assert_eq!(parse_positive_int("1MB"), Ok(1_000_000));
+    assert_eq!(parse_positive_int("1MiB"), Ok(1 << 20));
+    assert_eq!(parse_positive_int("1Z"), Err(SizeError::TooLarge));
+}
+
+#[test]
+fn test_parse_count() {
+    assert_eq!("3".parse(), Ok(Count::First(3)));
+    assert_eq!("-3".parse(), Ok(Count::AllButLast(3)));
+    assert_eq!("-2b".parse(), Ok(Count::AllButLast(1024)));
+    assert_eq!("-0".parse(), Ok(Count::AllButLast(0)));
+    assert_eq!("--3".parse::<Count>(), Err(SizeError::Invalid));
+    assert_eq!("foo".parse::<Count>(), Err(SizeError::Invalid));
+}
+
+#[test]
+fn test_all_but_last() {
+    let lines = |input: &str, count| {
+        let mut out = vec![];
+        let lines = Lines::new(input.as_bytes(), LineEnding::Lf, b'\n');
+        all_but_last_lines(lines, count, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(lines("a\nb\nc\n", 1), "a\nb\n");
+    assert_eq!(lines("a\nb\nc", 2), "a\n");
+    assert_eq!(lines("a\nb\n", 5), "");
+    assert_eq!(lines("a\r\nb\r\n", 1), "a\r\n");
+
+    let bytes = |input: &[u8], count| {
+        let mut out = vec![];
+        all_but_last_bytes(input, count, &mut out).unwrap();
+        out
+    };
+    assert_eq!(bytes(b"abcdef", 2), b"abcd");
+    assert_eq!(bytes(b"abc", 3), b"");
+    assert_eq!(bytes(&[7; 3 * BUFFER_SIZE], 10), [7; 3 * BUFFER_SIZE - 10]);
+}
+
+#[test]
+fn test_first_chars() {
+    let chars = |input: &[u8], count| {
+        let mut out = vec![];
+        first_chars(input, count, &mut out).unwrap();
+        out
+    };
+
+    assert_eq!(chars(b"hello", 3), b"hel");
+    assert_eq!(chars(b"hi", 3), b"hi");
+    assert_eq!(chars("Öne".as_bytes(), 1), "Ö".as_bytes());
+    assert_eq!(chars("日本語".as_bytes(), 2), "日本".as_bytes());
+    assert_eq!(chars("👍🏽!".as_bytes(), 2), "👍🏽".as_bytes());
+    assert_eq!(chars(b"\xffa\xfe", 2), b"\xffa");
+}
+
+#[test]
+fn test_header() {
+    use clap::Parser;
+
+    let app = App::parse_from(["headr", "-v"]);
+    assert_eq!(app.header("a.txt", 0), "==> a.txt <==");
+    assert_eq!(app.header("a.txt", 1), "\n==> a.txt <==");
+
+    let app = App::parse_from(["headr", "--header-format", "--- {file} ({size} bytes) ---"]);
+    assert_eq!(
+        app.header("tests/inputs/one.txt", 1),
+        "--- tests/inputs/one.txt (23 bytes) ---"
+    );
+    assert_eq!(app.header("{size}", 0), "--- {size} (? bytes) ---");
 }
@@ -0,0 +1,156 @@
+use std::io::{self, BufRead};
+use std::str::FromStr;
+
+/// Line terminator convention of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineEnding {
+    /// `\n`, as on Unix
+    Lf,
+    /// `\r\n`, as on Windows; a lone `\n` or `\r` does not end a line
+    Crlf,
+    /// `\r`, as on classic Mac OS
+    Cr,
+    /// Whichever of the above ends the first line of each file
+    Auto,
+}
+
+impl FromStr for LineEnding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lf" => Ok(Self::Lf),
+            "crlf" => Ok(Self::Crlf),
+            "cr" => Ok(Self::Cr),
+            "auto" => Ok(Self::Auto),
+            _ => Err(format!("invalid line ending: {}", s)),
+        }
+    }
+}
+
+/// How lines are split in one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Split {
+    On(u8),
+    Crlf,
+    /// Not detected yet
+    Auto,
+}
+
+/// Reads the lines of a file as raw bytes, terminators included
+pub(crate) struct Lines<R> {
+    file: R,
+    split: Split,
+}
+
+impl<R: BufRead> Lines<R> {
+    /// Splits `file` by `ending`, or on `terminator` with `-z`
+    pub(crate) fn new(file: R, ending: LineEnding, terminator: u8) -> Self {
+        let split = match ending {
+            LineEnding::Lf => Split::On(terminator),
+            LineEnding::Crlf => Split::Crlf,
+            LineEnding::Cr => Split::On(b'\r'),
+            LineEnding::Auto => Split::Auto,
+        };
+        Self { file, split }
+    }
+
+    /// Appends the next line to `buf`. The last line may have no terminator.
+    /// Returns the number of bytes read, which is 0 at the end of input.
+    pub(crate) fn read_line(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let start = buf.len();
+        match self.split {
+            Split::On(terminator) => self.file.read_until(terminator, buf),
+            Split::Crlf => loop {
+                let bytes = self.file.read_until(b'\n', buf)?;
+                if bytes == 0 || buf[start..].ends_with(b"\r\n") || !buf.ends_with(b"\n") {
+                    return Ok(buf.len() - start);
+                }
+            },
+            Split::Auto => self.read_first_line(buf, start),
+        }
+    }
+
+    /// Reads up to and including the first `\n`, `\r\n` or `\r`, and splits
+    /// the following lines the same way
+    fn read_first_line(&mut self, buf: &mut Vec<u8>, start: usize) -> io::Result<usize> {
+        loop {
+            let available = self.file.fill_buf()?;
+            if buf.len() > start && buf.ends_with(b"\r") {
+                // The \r was the last byte of the previous buffer
+                self.split = match available.first() {
+                    Some(b'\n') => {
+                        buf.push(b'\n');
+                        self.file.consume(1);
+                        Split::Crlf
+                    }
+                    _ => Split::On(b'\r'),
+                };
+                return Ok(buf.len() - start);
+            }
+            if available.is_empty() {
+                return Ok(buf.len() - start);
+            }
+
+            match available.iter().position(|&b| b == b'\n' || b == b'\r') {
+                Some(i) => {
+                    let newline = available[i] == b'\n';
+                    buf.extend_from_slice(&available[..=i]);
+                    self.file.consume(i + 1);
+                    if newline {
+                        self.split = Split::On(b'\n');
+                        return Ok(buf.len() - start);
+                    }
+                }
+                None => {
+                    let len = available.len();
+                    buf.extend_from_slice(available);
+                    self.file.consume(len);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineEnding, Lines};
+    use std::io::BufReader;
+
+    fn lines(input: &[u8], ending: LineEnding) -> Vec<String> {
+        // A tiny buffer so that terminators are split across reads
+        let mut lines = Lines::new(BufReader::with_capacity(2, input), ending, b'\n');
+        let mut result = vec![];
+        let mut line = vec![];
+        while lines.read_line(&mut line).unwrap() > 0 {
+            result.push(String::from_utf8(line.clone()).unwrap());
+            line.clear();
+        }
+        result
+    }
+
+    #[test]
+    fn test_line_ending() {
+        assert_eq!(lines(b"a\nb\r\nc", LineEnding::Lf), ["a\n", "b\r\n", "c"]);
+        assert_eq!(
+            lines(b"a\r\nb\nc\rd\r\ne", LineEnding::Crlf),
+            ["a\r\n", "b\nc\rd\r\n", "e"]
+        );
+        assert_eq!(lines(b"a\rb\nc\r", LineEnding::Cr), ["a\r", "b\nc\r"]);
+        assert_eq!("crlf".parse(), Ok(LineEnding::Crlf));
+        assert!("dos".parse::<LineEnding>().is_err());
+    }
+
+    #[test]
+    fn test_auto() {
+        assert_eq!(lines(b"a\nb\r\nc", LineEnding::Auto), ["a\n", "b\r\n", "c"]);
+        assert_eq!(
+            lines(b"ab\r\nc\nd\r\n", LineEnding::Auto),
+            ["ab\r\n", "c\nd\r\n"]
+        );
+        assert_eq!(lines(b"abc\rd\ne\r", LineEnding::Auto), ["abc\r", "d\ne\r"]);
+        assert_eq!(lines(b"abc\r", LineEnding::Auto), ["abc\r"]);
+        assert_eq!(lines(b"abc", LineEnding::Auto), ["abc"]);
+        assert_eq!(lines(b"", LineEnding::Auto), Vec::<String>::new());
+    }
+}
@@ -3,7 +3,6 @@ use headr::App;
 
 fn main() {
     if let Err(e) = App::parse().run() {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        e.exit();
     }
 }
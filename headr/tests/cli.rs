@@ -1,9 +1,10 @@
-use assert_cmd::Command;
+use assert_cmd::{cargo::cargo_bin, Command};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::error::Error;
-use std::fs::{self, File};
-use std::io::Read;
+use std::fs;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
 
 const PROG: &str = "headr";
 const EMPTY: &str = "tests/inputs/empty.txt";
@@ -11,6 +12,8 @@ const ONE: &str = "tests/inputs/one.txt";
 const TWO: &str = "tests/inputs/two.txt";
 const THREE: &str = "tests/inputs/three.txt";
 const TEN: &str = "tests/inputs/ten.txt";
+const CRLF: &str = "tests/inputs/windows.crlf";
+const CR: &str = "tests/inputs/mac.cr";
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -51,13 +54,45 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PROG)?
         .arg(&bad)
         .assert()
-        .success()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
 
     Ok(())
 }
 
 #[test]
+fn bad_file_among_good_files() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string(ONE)?;
+
+    Command::cargo_bin(PROG)?
+        .args([&bad, ONE])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(expected))
+        .stderr(predicate::str::starts_with(format!("{}: ", bad)));
+
+    Ok(())
+}
+
+#[test]
+fn directory_among_good_files() -> TestResult {
+    let expected = fs::read_to_string(ONE)?;
+
+    for jobs in ["1", "2"] {
+        Command::cargo_bin(PROG)?
+            .args(["--jobs", jobs, "tests/inputs", ONE])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains(expected.as_str()))
+            .stderr(predicate::str::starts_with("tests/inputs: "));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal line count -- {}", &bad);
@@ -72,6 +107,7 @@ fn dies_bad_lines() -> TestResult {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal byte count -- {}", &bad);
@@ -90,7 +126,7 @@ fn dies_bad_lines_and_bytes() -> TestResult {
     let expected = "The argument '--lines <LINES>' cannot be used with '--bytes <BYTES>'";
 
     Command::cargo_bin(PROG)?
-        .args(&["-n", "-c", "1", EMPTY])
+        .args(["-n", "1", "-c", "1", EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -99,33 +135,27 @@ fn dies_bad_lines_and_bytes() -> TestResult {
 }
 
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+    let expected = fs::read(expected_file)?;
 
     Command::cargo_bin(PROG)?
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(&expected as &[u8]));
 
     Ok(())
 }
 
 fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
-    let input = fs::read_to_string(input_file)?;
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+    let input = fs::read(input_file)?;
+    let expected = fs::read(expected_file)?;
 
     Command::cargo_bin(PROG)?
         .args(args)
         .write_stdin(input)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(&expected as &[u8]));
 
     Ok(())
 }
@@ -444,3 +474,385 @@ fn all_c4() -> TestResult {
         "tests/expected/all.c4.out",
     )
 }
+
+#[test]
+fn decompress() -> TestResult {
+    for ext in ["gz", "bz2", "xz", "zst"] {
+        let file = format!("{}.{}", TEN, ext);
+        run(&["-Z", &file], "tests/expected/ten.txt.out")?;
+        run(&["-Z", &file, "-n", "2"], "tests/expected/ten.txt.n2.out")?;
+        run(&["-Z", &file, "-c", "4"], "tests/expected/ten.txt.c4.out")?;
+    }
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args([TEN])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_bytes() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["-c", "4", TEN])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn null_data() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-n", "2"])
+        .write_stdin("one\ntwo\0three\0four\0")
+        .assert()
+        .success()
+        .stdout("one\ntwo\0three\0");
+    Ok(())
+}
+
+#[test]
+fn all_but_last_lines() -> TestResult {
+    for file in [EMPTY, ONE, TWO, THREE, TEN] {
+        let expected = file.replace("inputs", "expected") + ".n-2.out";
+        run(&["-n", "-2", file], &expected)?;
+        run_stdin(&["-n", "-2"], file, &expected)?;
+    }
+    run(
+        &["-n", "-2", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n-2.out",
+    )
+}
+
+#[test]
+fn all_but_last_bytes() -> TestResult {
+    for file in [EMPTY, ONE, TWO, THREE, TEN] {
+        let expected = file.replace("inputs", "expected") + ".c-4.out";
+        run(&["-c", "-4", file], &expected)?;
+        run_stdin(&["-c", "-4"], file, &expected)?;
+    }
+    run(
+        &["-c", "-4", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.c-4.out",
+    )
+}
+
+#[test]
+fn all_but_last_null_data() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-n", "-1"])
+        .write_stdin("one\ntwo\0three\0four\0")
+        .assert()
+        .success()
+        .stdout("one\ntwo\0three\0");
+    Ok(())
+}
+
+#[test]
+fn all_but_last_zero() -> TestResult {
+    run(&["-n", "-0", TEN], TEN)?;
+    run(&["-c", "-0", TEN], TEN)
+}
+
+#[test]
+fn dies_bad_negative_count() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-n", "--1", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal line count -- --1"));
+
+    Command::cargo_bin(PROG)?
+        .args(["-c", "-x", EMPTY])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn size_suffixes() -> TestResult {
+    run(&["-c", "1K", TEN], "tests/expected/ten.txt.out")?;
+    run(&["-n", "1KiB", TEN], "tests/expected/ten.txt.out")?;
+    run(&["-n", "-1K", TEN], "tests/expected/empty.txt.out")?;
+    run(&["-c", "-1b", TEN], "tests/expected/empty.txt.out")?;
+    run(&["--bytes=-1MB", TEN], "tests/expected/empty.txt.out")
+}
+
+#[test]
+fn dies_count_too_large() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-c", "1Z", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("byte count too large -- 1Z"));
+
+    Command::cargo_bin(PROG)?
+        .args(["-n", "99999999999999999999", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "line count too large -- 99999999999999999999",
+        ));
+    Ok(())
+}
+
+#[test]
+fn chars() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--chars", "2", ONE])
+        .assert()
+        .success()
+        .stdout("Ön");
+
+    Command::cargo_bin(PROG)?
+        .args(["--chars", "3"])
+        .write_stdin("日本語のテキスト")
+        .assert()
+        .success()
+        .stdout("日本語");
+    Ok(())
+}
+
+#[test]
+fn bytes_are_raw() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-c", "3"])
+        .write_stdin(&b"\xff\xfe\x00\x01"[..])
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"\xff\xfe\x00"[..]));
+
+    // Huge counts are not allocated up front
+    run(&["-c", "8E", TEN], "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn dies_bad_chars() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--chars", "0", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal character count -- 0"));
+
+    Command::cargo_bin(PROG)?
+        .args(["--chars", "1", "-c", "1", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn quiet() -> TestResult {
+    run(
+        &["-q", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.q.out",
+    )?;
+    run(
+        &["--silent", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.q.out",
+    )
+}
+
+#[test]
+fn verbose() -> TestResult {
+    run(&["-v", ONE], "tests/expected/one.txt.v.out")
+}
+
+#[test]
+fn header_format() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args([
+            "-n",
+            "1",
+            "--header-format",
+            "--- {file} ({size} bytes) ---",
+            ONE,
+            TWO,
+        ])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "--- tests/inputs/one.txt (23 bytes) ---\n",
+            "Öne line, four words.\n",
+            "--- tests/inputs/two.txt (23 bytes) ---\n",
+            "Two lines.\n",
+        ));
+
+    Command::cargo_bin(PROG)?
+        .args(["-v", "--header-format", "{file}: {size}"])
+        .write_stdin("piped\n")
+        .assert()
+        .success()
+        .stdout("-: ?\npiped\n");
+    Ok(())
+}
+
+#[test]
+fn dies_quiet_and_verbose() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-q", "-v", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn line_ending_crlf() -> TestResult {
+    for ending in ["crlf", "auto"] {
+        let args = ["--line-ending", ending, "-n", "3", CRLF];
+        run(&args, "tests/expected/windows.crlf.n3.out")?;
+        run_stdin(&args[..4], CRLF, "tests/expected/windows.crlf.n3.out")?;
+        run(
+            &["--line-ending", ending, "-n", "-1", CRLF],
+            "tests/expected/windows.crlf.n-1.out",
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn line_ending_cr() -> TestResult {
+    for ending in ["cr", "auto"] {
+        let args = ["--line-ending", ending, "-n", "2", CR];
+        run(&args, "tests/expected/mac.cr.n2.out")?;
+        run_stdin(&args[..4], CR, "tests/expected/mac.cr.n2.out")?;
+        run(
+            &["--line-ending", ending, "-n", "-1", CR],
+            "tests/expected/mac.cr.n-1.out",
+        )?;
+    }
+
+    // Without \n, the whole file is a single unterminated line
+    run(&["-n", "1", CR], CR)
+}
+
+#[test]
+fn line_ending_auto_per_file() -> TestResult {
+    let mut expected = b"==> tests/inputs/mac.cr <==\n".to_vec();
+    expected.extend(fs::read("tests/expected/mac.cr.n2.out")?);
+    expected.extend(b"\n==> tests/inputs/windows.crlf <==\none\r\ntwo\r\n");
+
+    Command::cargo_bin(PROG)?
+        .args(["--line-ending", "auto", "-n", "2", CR, CRLF])
+        .assert()
+        .success()
+        .stdout(predicate::eq(expected));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_line_ending() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--line-ending", "dos", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\"dos\""));
+
+    Command::cargo_bin(PROG)?
+        .args(["--line-ending", "cr", "-z", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn jobs() -> TestResult {
+    for jobs in ["1", "2", "8"] {
+        run(
+            &["-j", jobs, EMPTY, ONE, TWO, THREE, TEN],
+            "tests/expected/all.out",
+        )?;
+        run(
+            &["--jobs", jobs, "-n", "-2", EMPTY, ONE, TWO, THREE, TEN],
+            "tests/expected/all.n-2.out",
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn jobs_many_files() -> TestResult {
+    // More files than are read ahead, in an order unlike that of completion
+    let files: Vec<_> = [TEN, EMPTY, THREE, ONE, TWO].repeat(200);
+    let mut expected = vec![];
+    for (i, file) in files.iter().enumerate() {
+        if i > 0 {
+            expected.push(b'\n');
+        }
+        expected.extend(format!("==> {} <==\n", file).bytes());
+        expected.extend(fs::read(file.replace("inputs", "expected") + ".n2.out")?);
+    }
+
+    Command::cargo_bin(PROG)?
+        .args(["-j", "3", "-n", "2"])
+        .args(&files)
+        .assert()
+        .success()
+        .stdout(predicate::eq(expected));
+    Ok(())
+}
+
+#[test]
+fn jobs_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+
+    Command::cargo_bin(PROG)?
+        .args(["-j", "4", EMPTY, &bad, ONE, TWO, THREE, TEN])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!("{}: ", bad)))
+        .stdout(predicate::str::contains(
+            expected.split_once("\n\n").unwrap().1,
+        ));
+    Ok(())
+}
+
+#[test]
+fn jobs_broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let files = [TEN; 1000];
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["-j", "4"])
+        .args(files)
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn dies_bad_jobs() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-j", "0", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("illegal number of jobs -- 0"));
+    Ok(())
+}
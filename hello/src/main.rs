@@ -1,3 +1,15 @@
+use clr_common::AppResult;
+use std::io::Write;
+
+fn run() -> AppResult<()> {
+    let mut out = clr_common::stdout();
+    writeln!(out, "Hello, world!")?;
+    out.flush()?;
+    Ok(())
+}
+
 fn main() {
-    println!("Hello, world!");
+    if let Err(e) = run() {
+        e.exit();
+    }
 }
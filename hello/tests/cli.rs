@@ -1,4 +1,9 @@
-use assert_cmd::Command;
+use assert_cmd::{cargo::cargo_bin, Command};
+use std::error::Error;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+
+type TestResult = Result<(), Box<dyn Error>>;
 
 #[test]
 fn runs() {
@@ -17,3 +22,18 @@ fn false_not_ok() {
     let mut cmd = Command::cargo_bin("false").unwrap();
     cmd.assert().failure();
 }
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin("hello"))
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
@@ -0,0 +1,29 @@
+#! /bin/bash
+
+IN_DIR="tests/inputs"
+OUT_DIR="tests/expected"
+
+[[ ! -d "$OUT_DIR" ]] && mkdir -p "$OUT_DIR"
+
+for FILE in $IN_DIR/*.txt; do
+    BASENAME=$(basename "$FILE")
+    tail $FILE > ${OUT_DIR}/${BASENAME}.out
+    tail -n 0 $FILE > ${OUT_DIR}/${BASENAME}.n0.out
+    tail -n 1 $FILE > ${OUT_DIR}/${BASENAME}.n1.out
+    tail -n 3 $FILE > ${OUT_DIR}/${BASENAME}.n3.out
+    tail -n +0 $FILE > ${OUT_DIR}/${BASENAME}.n+0.out
+    tail -n +1 $FILE > ${OUT_DIR}/${BASENAME}.n+1.out
+    tail -n +3 $FILE > ${OUT_DIR}/${BASENAME}.n+3.out
+    tail -c 0 $FILE > ${OUT_DIR}/${BASENAME}.c0.out
+    tail -c 3 $FILE > ${OUT_DIR}/${BASENAME}.c3.out
+    tail -c 8 $FILE > ${OUT_DIR}/${BASENAME}.c8.out
+    tail -c +1 $FILE > ${OUT_DIR}/${BASENAME}.c+1.out
+    tail -c +8 $FILE > ${OUT_DIR}/${BASENAME}.c+8.out
+done
+
+ALL="$IN_DIR/empty.txt $IN_DIR/one.txt $IN_DIR/two.txt $IN_DIR/three.txt $IN_DIR/ten.txt"
+tail $ALL > ${OUT_DIR}/all.out
+tail -n 1 $ALL > ${OUT_DIR}/all.n1.out
+tail -n +3 $ALL > ${OUT_DIR}/all.n+3.out
+tail -c 3 $ALL > ${OUT_DIR}/all.c3.out
+tail -c +8 $ALL > ${OUT_DIR}/all.c+8.out
@@ -0,0 +1,242 @@
+use clr_common::{AppResult, Output};
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+use inotify::{Inotify, WatchMask};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+
+const BUFFER_SIZE: usize = 8192;
+
+/// A file being followed after its initial tail has been printed
+pub(crate) struct Followed {
+    name: String,
+    file: Option<File>,
+    pos: u64,
+    id: Option<(u64, u64)>,
+}
+
+impl Followed {
+    /// Follows an open file from its current position
+    pub(crate) fn new(name: &str, mut file: File) -> io::Result<Self> {
+        let pos = file.stream_position()?;
+        let id = file_id(&file.metadata()?);
+
+        Ok(Self {
+            name: name.to_string(),
+            file: Some(file),
+            pos,
+            id: Some(id),
+        })
+    }
+
+    /// Waits for a file that could not be opened to appear (`-F` only)
+    pub(crate) fn missing(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            file: None,
+            pos: 0,
+            id: None,
+        }
+    }
+
+    /// Reopens the file by name if it has been replaced or has appeared.
+    /// Returns whether a new file is now being followed.
+    fn reopen(&mut self) -> bool {
+        match fs::metadata(&self.name) {
+            Err(e) => {
+                if self.file.take().is_some() {
+                    eprintln!("{}: {}", self.name, e);
+                    self.id = None;
+                }
+                false
+            }
+            Ok(metadata) => {
+                let id = file_id(&metadata);
+                if self.id == Some(id) {
+                    return false;
+                }
+
+                match File::open(&self.name) {
+                    Err(_) => false,
+                    Ok(file) => {
+                        if self.file.is_some() {
+                            eprintln!("{}: file replaced; following new file", self.name);
+                        } else {
+                            eprintln!("{}: file appeared; following new file", self.name);
+                        }
+                        self.file = Some(file);
+                        self.pos = 0;
+                        self.id = Some(id);
+                        true
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies any data appended since the last read, preceded by a header if
+    /// `header` is set. Returns whether anything was written.
+    fn copy_new(&mut self, out: &mut impl Write, header: bool) -> io::Result<bool> {
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return Ok(false),
+        };
+
+        if file.metadata()?.len() < self.pos {
+            eprintln!("{}: file truncated", self.name);
+            self.pos = file.seek(SeekFrom::Start(0))?;
+        }
+
+        let mut buffer = [0; BUFFER_SIZE];
+        let bytes = file.read(&mut buffer)?;
+        if bytes == 0 {
+            return Ok(false);
+        }
+
+        if header {
+            writeln!(out, "\n==> {} <==", self.name)?;
+        }
+        out.write_all(&buffer[..bytes])?;
+        self.pos += bytes as u64 + io::copy(file, out)?;
+        Ok(true)
+    }
+}
+
+/// Blocks between checks for appended data
+pub(crate) enum Watcher {
+    Poll(Duration),
+    #[cfg(target_os = "linux")]
+    Inotify(Inotify, Duration),
+}
+
+impl Watcher {
+    /// Sleeps for `interval` between checks
+    pub(crate) fn poll(interval: Duration) -> Self {
+        Self::Poll(interval)
+    }
+
+    /// Wakes up as soon as the directory of any followed file changes, or
+    /// after `interval` at the latest
+    #[cfg(target_os = "linux")]
+    pub(crate) fn inotify(files: &[Followed], interval: Duration) -> io::Result<Self> {
+        let inotify = Inotify::init()?;
+        let mask = WatchMask::MODIFY
+            | WatchMask::ATTRIB
+            | WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO;
+
+        for followed in files {
+            let dir = match Path::new(&followed.name).parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            inotify.watches().add(dir, mask)?;
+        }
+
+        Ok(Self::Inotify(inotify, interval))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn inotify(_files: &[Followed], _interval: Duration) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "inotify is not supported on this platform",
+        ))
+    }
+
+    fn wait(&mut self) -> io::Result<()> {
+        match self {
+            Self::Poll(interval) => thread::sleep(*interval),
+            #[cfg(target_os = "linux")]
+            Self::Inotify(inotify, interval) => {
+                let mut fds = libc::pollfd {
+                    fd: inotify.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                let timeout = interval.as_millis().min(i32::MAX as u128) as i32;
+                if unsafe { libc::poll(&mut fds, 1, timeout) } < 0 {
+                    let e = io::Error::last_os_error();
+                    if e.kind() != io::ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+
+                let mut buffer = [0; 4096];
+                loop {
+                    match inotify.read_events(&mut buffer) {
+                        Ok(events) => {
+                            if events.count() == 0 {
+                                break;
+                            }
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints data appended to `files` until the process is killed, or until
+/// process `pid` has died. `last` is the index of the file whose output was
+/// printed last, so that headers are only printed when the source changes.
+pub(crate) fn follow(
+    mut files: Vec<Followed>,
+    by_name: bool,
+    headers: bool,
+    mut last: Option<usize>,
+    mut watcher: Watcher,
+    pid: Option<i32>,
+    out: &mut Output,
+) -> AppResult<()> {
+    loop {
+        // Checked before reading so that anything written before the
+        // process died is still printed
+        let alive = pid.is_none_or(is_alive);
+        let mut changed = false;
+
+        for (i, followed) in files.iter_mut().enumerate() {
+            if followed.copy_new(out, headers && last != Some(i))? {
+                last = Some(i);
+                changed = true;
+            }
+
+            if by_name && followed.reopen() {
+                changed = true;
+            }
+        }
+
+        out.flush()?;
+
+        if !alive {
+            return Ok(());
+        }
+
+        if !changed {
+            watcher.wait()?;
+        }
+    }
+}
+
+fn is_alive(pid: i32) -> bool {
+    // Signal 0 only checks whether the process exists; EPERM means it exists
+    // but belongs to another user
+    let status = unsafe { libc::kill(pid, 0) };
+    status == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+fn file_id(metadata: &Metadata) -> (u64, u64) {
+    (metadata.dev(), metadata.ino())
+}
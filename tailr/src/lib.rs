@@ -1,13 +1,487 @@
+mod follow;
+
 use clap::Parser;
-use std::error::Error;
+use clr_common::{
+    copy, parse_size, report, terminator, AppResult, Error, Named, Output, SizeError,
+};
+use follow::{Followed, Watcher};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+const BLOCK_SIZE: u64 = 8192;
 
-type AppResult<T> = Result<T, Box<dyn Error>>;
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum TakeValue {
+    /// Start at the given 1-based position (`+N`)
+    FromStart(u64),
+    /// Take the last N lines or bytes
+    FromEnd(u64),
+}
 
 #[derive(Debug, Parser)]
-pub struct App {}
+#[clap(
+    version = "0.1.0",
+    author = "akthrms <akt.hrms@gmail.com>",
+    about = "Rust tail"
+)]
+pub struct App {
+    /// Input file(s)
+    #[clap(value_name = "FILE", multiple_occurrences = true, default_value = "-")]
+    files: Vec<String>,
+
+    /// Number of lines, with an optional multiplier suffix such as K or MiB
+    #[clap(
+        short = 'n',
+        long = "lines",
+        value_name = "LINES",
+        default_value = "10",
+        allow_hyphen_values = true,
+        parse(try_from_str = parse_lines)
+    )]
+    lines: TakeValue,
+
+    /// Number of bytes, with an optional multiplier suffix such as K or MiB
+    #[clap(
+        short = 'c',
+        long = "bytes",
+        value_name = "BYTES",
+        takes_value = true,
+        allow_hyphen_values = true,
+        conflicts_with = "lines",
+        parse(try_from_str = parse_bytes)
+    )]
+    bytes: Option<TakeValue>,
+
+    /// Output appended data as the file grows
+    #[clap(short = 'f', long = "follow", takes_value = false)]
+    follow: bool,
+
+    /// Follow by name, reopening the file if it is replaced or truncated
+    #[clap(short = 'F', takes_value = false)]
+    follow_name: bool,
+
+    /// Wait for changes with inotify instead of polling (Linux only)
+    #[clap(long = "inotify", takes_value = false)]
+    inotify: bool,
+
+    /// Seconds to sleep between checks when following
+    #[clap(
+        short = 's',
+        long = "sleep-interval",
+        value_name = "SECONDS",
+        default_value = "1.0",
+        parse(try_from_str = parse_sleep_interval)
+    )]
+    sleep_interval: Duration,
+
+    /// Stop following once process PID has died
+    #[clap(long = "pid", value_name = "PID", parse(try_from_str = parse_pid))]
+    pid: Option<i32>,
+
+    /// Split and write records on NUL instead of newline
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
+}
+
+enum Input {
+    Seekable(File),
+    Stream(Box<dyn BufRead>),
+}
 
 impl App {
     pub fn run(self) -> AppResult<()> {
+        let mut out = clr_common::stdout();
+        let follow = self.follow || self.follow_name;
+        let mut followed = vec![];
+        let mut last = None;
+        let mut failed = false;
+
+        for (file_num, filename) in self.files.iter().enumerate() {
+            match open(filename) {
+                Err(e) => {
+                    eprintln!("{}", e);
+                    failed = true;
+                    if self.follow_name && filename != "-" {
+                        followed.push(Followed::missing(filename));
+                    }
+                }
+                Ok(mut input) => {
+                    if self.files.len() > 1 {
+                        writeln!(
+                            out,
+                            "{}==> {} <==",
+                            if file_num > 0 { "\n" } else { "" },
+                            filename
+                        )?;
+                    }
+
+                    let result = match self.bytes {
+                        Some(bytes) => print_bytes(filename, &mut input, bytes, &mut out),
+                        None => {
+                            let terminator = terminator(self.null_data);
+                            print_lines(filename, &mut input, self.lines, terminator, &mut out)
+                        }
+                    };
+                    if report(result)? {
+                        failed = true;
+                        continue;
+                    }
+
+                    out.flush()?;
+
+                    if let (true, Input::Seekable(file)) = (follow, input) {
+                        last = Some(followed.len());
+                        followed.push(Followed::new(filename, file)?);
+                    }
+                }
+            }
+        }
+
+        if !followed.is_empty() {
+            self.follow(followed, last, &mut out)?;
+        }
+
+        if failed {
+            return Err(Error::Exit(1));
+        }
         Ok(())
     }
+
+    fn follow(
+        &self,
+        followed: Vec<Followed>,
+        last: Option<usize>,
+        out: &mut Output,
+    ) -> AppResult<()> {
+        let watcher = if self.inotify {
+            Watcher::inotify(&followed, self.sleep_interval)?
+        } else {
+            Watcher::poll(self.sleep_interval)
+        };
+
+        follow::follow(
+            followed,
+            self.follow_name,
+            self.files.len() > 1,
+            last,
+            watcher,
+            self.pid,
+            out,
+        )
+    }
+}
+
+fn open(filename: &str) -> AppResult<Input> {
+    let input_error = |source| Error::Input {
+        path: filename.to_string(),
+        source,
+    };
+
+    match filename {
+        "-" => Ok(Input::Stream(Box::new(Named::new(
+            filename,
+            BufReader::new(io::stdin()),
+        )))),
+        _ => {
+            let file = File::open(filename).map_err(input_error)?;
+            if file.metadata().map_err(input_error)?.is_file() {
+                Ok(Input::Seekable(file))
+            } else {
+                Ok(Input::Stream(Box::new(Named::new(
+                    filename,
+                    BufReader::new(file),
+                ))))
+            }
+        }
+    }
+}
+
+fn parse_lines(val: &str) -> Result<TakeValue, String> {
+    parse_take_value(val).map_err(|e| e.message("line count", val))
+}
+
+fn parse_bytes(val: &str) -> Result<TakeValue, String> {
+    parse_take_value(val).map_err(|e| e.message("byte count", val))
+}
+
+fn parse_take_value(val: &str) -> Result<TakeValue, SizeError> {
+    match val.strip_prefix('+') {
+        Some(num) => parse_size(num).map(TakeValue::FromStart),
+        None => parse_size(val.strip_prefix('-').unwrap_or(val)).map(TakeValue::FromEnd),
+    }
+}
+
+fn parse_sleep_interval(val: &str) -> Result<Duration, String> {
+    match val.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs >= 0.0 => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(format!("invalid number of seconds -- {}", val)),
+    }
+}
+
+fn parse_pid(val: &str) -> Result<i32, String> {
+    match val.parse() {
+        Ok(pid) if pid > 0 => Ok(pid),
+        _ => Err(format!("invalid PID -- {}", val)),
+    }
+}
+
+/// Prints the lines of `input` selected by `take`. Errors reading `input`
+/// are `Error::Input` errors.
+fn print_lines(
+    filename: &str,
+    input: &mut Input,
+    take: TakeValue,
+    terminator: u8,
+    out: &mut impl Write,
+) -> AppResult<()> {
+    let input_error = |source| Error::Input {
+        path: filename.to_string(),
+        source,
+    };
+
+    match (input, take) {
+        (Input::Seekable(file), TakeValue::FromEnd(num)) => {
+            let start = find_line_offset(file, num, terminator).map_err(input_error)?;
+            file.seek(SeekFrom::Start(start)).map_err(input_error)?;
+            copy(filename, file, out)?;
+        }
+        (Input::Seekable(file), TakeValue::FromStart(num)) => {
+            let file = Named::new(filename, BufReader::new(file));
+            skip_lines(file, num, terminator, out)?;
+        }
+        (Input::Stream(file), TakeValue::FromStart(num)) => {
+            skip_lines(file, num, terminator, out)?;
+        }
+        (Input::Stream(file), TakeValue::FromEnd(num)) => {
+            last_lines(file, num, terminator, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the bytes of `input` selected by `take`. Errors reading `input`
+/// are `Error::Input` errors.
+fn print_bytes(
+    filename: &str,
+    input: &mut Input,
+    take: TakeValue,
+    out: &mut impl Write,
+) -> AppResult<()> {
+    let input_error = |source| Error::Input {
+        path: filename.to_string(),
+        source,
+    };
+
+    match (input, take) {
+        (Input::Seekable(file), TakeValue::FromEnd(num)) => {
+            let len = file.seek(SeekFrom::End(0)).map_err(input_error)?;
+            file.seek(SeekFrom::Start(len.saturating_sub(num)))
+                .map_err(input_error)?;
+            copy(filename, file, out)?;
+        }
+        (Input::Seekable(file), TakeValue::FromStart(num)) => {
+            file.seek(SeekFrom::Start(num.saturating_sub(1)))
+                .map_err(input_error)?;
+            copy(filename, file, out)?;
+        }
+        (Input::Stream(file), TakeValue::FromStart(num)) => {
+            io::copy(
+                &mut file.by_ref().take(num.saturating_sub(1)),
+                &mut io::sink(),
+            )?;
+            io::copy(file, out)?;
+        }
+        (Input::Stream(file), TakeValue::FromEnd(num)) => {
+            last_bytes(file, num, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Scans backwards from the end of `file` and returns the offset at which the
+/// last `num` lines start. Lines end with `terminator`, and a trailing
+/// terminator does not start a new line.
+fn find_line_offset<R: Read + Seek>(file: &mut R, num: u64, terminator: u8) -> io::Result<u64> {
+    let len = file.seek(SeekFrom::End(0))?;
+    if num == 0 {
+        return Ok(len);
+    }
+
+    let mut buffer = vec![0; BLOCK_SIZE as usize];
+    let mut pos = len;
+    let mut newlines = 0;
+
+    while pos > 0 {
+        let size = BLOCK_SIZE.min(pos);
+        pos -= size;
+
+        let block = &mut buffer[..size as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(block)?;
+
+        for (i, _) in block
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, &b)| b == terminator)
+        {
+            let offset = pos + i as u64;
+            if offset + 1 == len {
+                continue;
+            }
+
+            newlines += 1;
+            if newlines == num {
+                return Ok(offset + 1);
+            }
+        }
+    }
+
+    Ok(0)
+}
+
+fn skip_lines(
+    mut file: impl BufRead,
+    num: u64,
+    terminator: u8,
+    out: &mut impl Write,
+) -> AppResult<()> {
+    let mut line = vec![];
+    for _ in 1..num {
+        if file.read_until(terminator, &mut line)? == 0 {
+            return Ok(());
+        }
+        line.clear();
+    }
+    io::copy(&mut file, out)?;
+    Ok(())
+}
+
+fn last_lines(
+    mut file: impl BufRead,
+    num: u64,
+    terminator: u8,
+    out: &mut impl Write,
+) -> AppResult<()> {
+    if num == 0 {
+        return Ok(());
+    }
+
+    let mut lines = VecDeque::new();
+    loop {
+        let mut line = vec![];
+        if file.read_until(terminator, &mut line)? == 0 {
+            break;
+        }
+        if lines.len() as u64 == num {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    for line in lines {
+        out.write_all(&line)?;
+    }
+    Ok(())
+}
+
+fn last_bytes(mut file: impl Read, num: u64, out: &mut impl Write) -> AppResult<()> {
+    let num = usize::try_from(num).unwrap_or(usize::MAX);
+    let mut bytes = vec![];
+    let mut buffer = vec![0; BLOCK_SIZE as usize];
+
+    loop {
+        let size = file.read(&mut buffer)?;
+        if size == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&buffer[..size]);
+        if bytes.len() > num.saturating_mul(2).max(BLOCK_SIZE as usize) {
+            bytes.drain(..bytes.len() - num);
+        }
+    }
+
+    out.write_all(&bytes[bytes.len().saturating_sub(num)..])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        find_line_offset, parse_pid, parse_sleep_interval, parse_take_value, TakeValue, BLOCK_SIZE,
+    };
+    use clr_common::SizeError;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_take_value() {
+        assert_eq!(parse_take_value("3"), Ok(TakeValue::FromEnd(3)));
+        assert_eq!(parse_take_value("-3"), Ok(TakeValue::FromEnd(3)));
+        assert_eq!(parse_take_value("+3"), Ok(TakeValue::FromStart(3)));
+        assert_eq!(parse_take_value("+0"), Ok(TakeValue::FromStart(0)));
+        assert_eq!(parse_take_value("0"), Ok(TakeValue::FromEnd(0)));
+
+        assert_eq!(parse_take_value("-2K"), Ok(TakeValue::FromEnd(2048)));
+        assert_eq!(
+            parse_take_value("+1MB"),
+            Ok(TakeValue::FromStart(1_000_000))
+        );
+
+        for bad in ["", "+", "-", "foo", "3.5", "+-3", "-+3", "--3", "3X"] {
+            assert_eq!(parse_take_value(bad), Err(SizeError::Invalid));
+        }
+        assert_eq!(parse_take_value("-1Z"), Err(SizeError::TooLarge));
+    }
+
+    #[test]
+    fn test_parse_sleep_interval() {
+        assert_eq!(parse_sleep_interval("1"), Ok(Duration::from_secs(1)));
+        assert_eq!(parse_sleep_interval("0.25"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_sleep_interval("0"), Ok(Duration::ZERO));
+
+        for bad in ["", "-1", "foo", "inf", "NaN"] {
+            assert_eq!(
+                parse_sleep_interval(bad),
+                Err(format!("invalid number of seconds -- {}", bad))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_pid() {
+        assert_eq!(parse_pid("42"), Ok(42));
+
+        for bad in ["", "0", "-1", "foo", "99999999999"] {
+            assert_eq!(parse_pid(bad), Err(format!("invalid PID -- {}", bad)));
+        }
+    }
+
+    #[test]
+    fn test_find_line_offset() {
+        let mut file = Cursor::new("one\ntwo\nthree\n");
+        assert_eq!(find_line_offset(&mut file, 0, b'\n').unwrap(), 14);
+        assert_eq!(find_line_offset(&mut file, 1, b'\n').unwrap(), 8);
+        assert_eq!(find_line_offset(&mut file, 2, b'\n').unwrap(), 4);
+        assert_eq!(find_line_offset(&mut file, 3, b'\n').unwrap(), 0);
+        assert_eq!(find_line_offset(&mut file, 4, b'\n').unwrap(), 0);
+
+        let mut file = Cursor::new("one\ntwo\nthree");
+        assert_eq!(find_line_offset(&mut file, 1, b'\n').unwrap(), 8);
+        assert_eq!(find_line_offset(&mut file, 2, b'\n').unwrap(), 4);
+
+        let line = format!("{}\n", "x".repeat(BLOCK_SIZE as usize - 1));
+        let mut file = Cursor::new(line.repeat(3));
+        assert_eq!(
+            find_line_offset(&mut file, 1, b'\n').unwrap(),
+            2 * BLOCK_SIZE
+        );
+        assert_eq!(find_line_offset(&mut file, 2, b'\n').unwrap(), BLOCK_SIZE);
+
+        let mut file = Cursor::new("one\ntwo\0three\0");
+        assert_eq!(find_line_offset(&mut file, 1, b'\0').unwrap(), 8);
+        assert_eq!(find_line_offset(&mut file, 2, b'\0').unwrap(), 0);
+    }
 }
@@ -0,0 +1,903 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use rand::{distributions::Alphanumeric, Rng};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command as StdCommand, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const PROG: &str = "tailr";
+const EMPTY: &str = "tests/inputs/empty.txt";
+const ONE: &str = "tests/inputs/one.txt";
+const TWO: &str = "tests/inputs/two.txt";
+const THREE: &str = "tests/inputs/three.txt";
+const TEN: &str = "tests/inputs/ten.txt";
+const NO_NEWLINE: &str = "tests/inputs/no_newline.txt";
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+fn random_string() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(7)
+        .map(char::from)
+        .collect()
+}
+
+fn gen_bad_file() -> String {
+    loop {
+        let filename: String = random_string();
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+#[test]
+fn skips_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", bad);
+
+    Command::cargo_bin(PROG)?
+        .arg(&bad)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_match(expected)?);
+
+    Ok(())
+}
+
+#[test]
+fn bad_file_among_good_files() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string(ONE)?;
+
+    Command::cargo_bin(PROG)?
+        .args([&bad, ONE])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(expected))
+        .stderr(predicate::str::starts_with(format!("{}: ", bad)));
+
+    Ok(())
+}
+
+#[test]
+fn directory_among_good_files() -> TestResult {
+    let expected = fs::read_to_string(ONE)?;
+
+    for args in [["-n", "1"], ["-c", "+1"]] {
+        Command::cargo_bin(PROG)?
+            .args(args)
+            .args(["tests/inputs", ONE])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::ends_with(expected.as_str()))
+            .stderr(predicate::str::starts_with("tests/inputs: "));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_lines() -> TestResult {
+    let bad = random_string();
+    let expected = format!("illegal line count -- {}", &bad);
+
+    Command::cargo_bin(PROG)?
+        .args(["-n", &bad, EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_bytes() -> TestResult {
+    let bad = random_string();
+    let expected = format!("illegal byte count -- {}", &bad);
+
+    Command::cargo_bin(PROG)?
+        .args(["-c", &bad, EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_lines_and_bytes() -> TestResult {
+    let expected = "The argument '--lines <LINES>' cannot be used with '--bytes <BYTES>'";
+
+    Command::cargo_bin(PROG)?
+        .args(["-n", "1", "-c", "1", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+
+    Command::cargo_bin(PROG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
+    let input = fs::read(input_file)?;
+    let expected = fs::read(expected_file)?;
+
+    Command::cargo_bin(PROG)?
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn empty() -> TestResult {
+    run(&[EMPTY], "tests/expected/empty.txt.out")
+}
+
+#[test]
+fn empty_n1() -> TestResult {
+    run(&["-n", "1", EMPTY], "tests/expected/empty.txt.n1.out")
+}
+
+#[test]
+fn empty_n3() -> TestResult {
+    run(&["-n", "3", EMPTY], "tests/expected/empty.txt.n3.out")
+}
+
+#[test]
+fn empty_n_plus1() -> TestResult {
+    run(&["-n", "+1", EMPTY], "tests/expected/empty.txt.n+1.out")
+}
+
+#[test]
+fn empty_n_plus3() -> TestResult {
+    run(&["-n", "+3", EMPTY], "tests/expected/empty.txt.n+3.out")
+}
+
+#[test]
+fn empty_c0() -> TestResult {
+    run(&["-c", "0", EMPTY], "tests/expected/empty.txt.c0.out")
+}
+
+#[test]
+fn empty_c3() -> TestResult {
+    run(&["-c", "3", EMPTY], "tests/expected/empty.txt.c3.out")
+}
+
+#[test]
+fn empty_c_plus8() -> TestResult {
+    run(&["-c", "+8", EMPTY], "tests/expected/empty.txt.c+8.out")
+}
+
+#[test]
+fn one() -> TestResult {
+    run(&[ONE], "tests/expected/one.txt.out")
+}
+
+#[test]
+fn one_n1() -> TestResult {
+    run(&["-n", "1", ONE], "tests/expected/one.txt.n1.out")
+}
+
+#[test]
+fn one_n3() -> TestResult {
+    run(&["-n", "3", ONE], "tests/expected/one.txt.n3.out")
+}
+
+#[test]
+fn one_n_plus1() -> TestResult {
+    run(&["-n", "+1", ONE], "tests/expected/one.txt.n+1.out")
+}
+
+#[test]
+fn one_n_plus3() -> TestResult {
+    run(&["-n", "+3", ONE], "tests/expected/one.txt.n+3.out")
+}
+
+#[test]
+fn one_c0() -> TestResult {
+    run(&["-c", "0", ONE], "tests/expected/one.txt.c0.out")
+}
+
+#[test]
+fn one_c3() -> TestResult {
+    run(&["-c", "3", ONE], "tests/expected/one.txt.c3.out")
+}
+
+#[test]
+fn one_c_plus8() -> TestResult {
+    run(&["-c", "+8", ONE], "tests/expected/one.txt.c+8.out")
+}
+
+#[test]
+fn two() -> TestResult {
+    run(&[TWO], "tests/expected/two.txt.out")
+}
+
+#[test]
+fn two_n1() -> TestResult {
+    run(&["-n", "1", TWO], "tests/expected/two.txt.n1.out")
+}
+
+#[test]
+fn two_n3() -> TestResult {
+    run(&["-n", "3", TWO], "tests/expected/two.txt.n3.out")
+}
+
+#[test]
+fn two_n_plus1() -> TestResult {
+    run(&["-n", "+1", TWO], "tests/expected/two.txt.n+1.out")
+}
+
+#[test]
+fn two_n_plus3() -> TestResult {
+    run(&["-n", "+3", TWO], "tests/expected/two.txt.n+3.out")
+}
+
+#[test]
+fn two_c0() -> TestResult {
+    run(&["-c", "0", TWO], "tests/expected/two.txt.c0.out")
+}
+
+#[test]
+fn two_c3() -> TestResult {
+    run(&["-c", "3", TWO], "tests/expected/two.txt.c3.out")
+}
+
+#[test]
+fn two_c_plus8() -> TestResult {
+    run(&["-c", "+8", TWO], "tests/expected/two.txt.c+8.out")
+}
+
+#[test]
+fn three() -> TestResult {
+    run(&[THREE], "tests/expected/three.txt.out")
+}
+
+#[test]
+fn three_n0() -> TestResult {
+    run(&["-n", "0", THREE], "tests/expected/three.txt.n0.out")
+}
+
+#[test]
+fn three_n1() -> TestResult {
+    run(&["-n", "1", THREE], "tests/expected/three.txt.n1.out")
+}
+
+#[test]
+fn three_n3() -> TestResult {
+    run(&["-n", "3", THREE], "tests/expected/three.txt.n3.out")
+}
+
+#[test]
+fn three_n_minus3() -> TestResult {
+    run(&["-n", "-3", THREE], "tests/expected/three.txt.n3.out")
+}
+
+#[test]
+fn three_n_plus0() -> TestResult {
+    run(&["-n", "+0", THREE], "tests/expected/three.txt.n+0.out")
+}
+
+#[test]
+fn three_n_plus1() -> TestResult {
+    run(&["-n", "+1", THREE], "tests/expected/three.txt.n+1.out")
+}
+
+#[test]
+fn three_n_plus3() -> TestResult {
+    run(&["-n", "+3", THREE], "tests/expected/three.txt.n+3.out")
+}
+
+#[test]
+fn three_c0() -> TestResult {
+    run(&["-c", "0", THREE], "tests/expected/three.txt.c0.out")
+}
+
+#[test]
+fn three_c3() -> TestResult {
+    run(&["-c", "3", THREE], "tests/expected/three.txt.c3.out")
+}
+
+#[test]
+fn three_c8() -> TestResult {
+    run(&["-c", "8", THREE], "tests/expected/three.txt.c8.out")
+}
+
+#[test]
+fn three_c_plus1() -> TestResult {
+    run(&["-c", "+1", THREE], "tests/expected/three.txt.c+1.out")
+}
+
+#[test]
+fn three_c_plus8() -> TestResult {
+    run(&["-c", "+8", THREE], "tests/expected/three.txt.c+8.out")
+}
+
+#[test]
+fn ten() -> TestResult {
+    run(&[TEN], "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn ten_n0() -> TestResult {
+    run(&["-n", "0", TEN], "tests/expected/ten.txt.n0.out")
+}
+
+#[test]
+fn ten_n1() -> TestResult {
+    run(&["-n", "1", TEN], "tests/expected/ten.txt.n1.out")
+}
+
+#[test]
+fn ten_n3() -> TestResult {
+    run(&["-n", "3", TEN], "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn ten_n_minus3() -> TestResult {
+    run(&["-n", "-3", TEN], "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn ten_n_plus0() -> TestResult {
+    run(&["-n", "+0", TEN], "tests/expected/ten.txt.n+0.out")
+}
+
+#[test]
+fn ten_n_plus1() -> TestResult {
+    run(&["-n", "+1", TEN], "tests/expected/ten.txt.n+1.out")
+}
+
+#[test]
+fn ten_n_plus3() -> TestResult {
+    run(&["-n", "+3", TEN], "tests/expected/ten.txt.n+3.out")
+}
+
+#[test]
+fn ten_c0() -> TestResult {
+    run(&["-c", "0", TEN], "tests/expected/ten.txt.c0.out")
+}
+
+#[test]
+fn ten_c3() -> TestResult {
+    run(&["-c", "3", TEN], "tests/expected/ten.txt.c3.out")
+}
+
+#[test]
+fn ten_c8() -> TestResult {
+    run(&["-c", "8", TEN], "tests/expected/ten.txt.c8.out")
+}
+
+#[test]
+fn ten_c_plus1() -> TestResult {
+    run(&["-c", "+1", TEN], "tests/expected/ten.txt.c+1.out")
+}
+
+#[test]
+fn ten_c_plus8() -> TestResult {
+    run(&["-c", "+8", TEN], "tests/expected/ten.txt.c+8.out")
+}
+
+#[test]
+fn no_newline() -> TestResult {
+    run(&[NO_NEWLINE], "tests/expected/no_newline.txt.out")
+}
+
+#[test]
+fn no_newline_n0() -> TestResult {
+    run(
+        &["-n", "0", NO_NEWLINE],
+        "tests/expected/no_newline.txt.n0.out",
+    )
+}
+
+#[test]
+fn no_newline_n1() -> TestResult {
+    run(
+        &["-n", "1", NO_NEWLINE],
+        "tests/expected/no_newline.txt.n1.out",
+    )
+}
+
+#[test]
+fn no_newline_n3() -> TestResult {
+    run(
+        &["-n", "3", NO_NEWLINE],
+        "tests/expected/no_newline.txt.n3.out",
+    )
+}
+
+#[test]
+fn no_newline_n_minus3() -> TestResult {
+    run(
+        &["-n", "-3", NO_NEWLINE],
+        "tests/expected/no_newline.txt.n3.out",
+    )
+}
+
+#[test]
+fn no_newline_n_plus0() -> TestResult {
+    run(
+        &["-n", "+0", NO_NEWLINE],
+        "tests/expected/no_newline.txt.n+0.out",
+    )
+}
+
+#[test]
+fn no_newline_n_plus1() -> TestResult {
+    run(
+        &["-n", "+1", NO_NEWLINE],
+        "tests/expected/no_newline.txt.n+1.out",
+    )
+}
+
+#[test]
+fn no_newline_n_plus3() -> TestResult {
+    run(
+        &["-n", "+3", NO_NEWLINE],
+        "tests/expected/no_newline.txt.n+3.out",
+    )
+}
+
+#[test]
+fn no_newline_c0() -> TestResult {
+    run(
+        &["-c", "0", NO_NEWLINE],
+        "tests/expected/no_newline.txt.c0.out",
+    )
+}
+
+#[test]
+fn no_newline_c3() -> TestResult {
+    run(
+        &["-c", "3", NO_NEWLINE],
+        "tests/expected/no_newline.txt.c3.out",
+    )
+}
+
+#[test]
+fn no_newline_c8() -> TestResult {
+    run(
+        &["-c", "8", NO_NEWLINE],
+        "tests/expected/no_newline.txt.c8.out",
+    )
+}
+
+#[test]
+fn no_newline_c_plus1() -> TestResult {
+    run(
+        &["-c", "+1", NO_NEWLINE],
+        "tests/expected/no_newline.txt.c+1.out",
+    )
+}
+
+#[test]
+fn no_newline_c_plus8() -> TestResult {
+    run(
+        &["-c", "+8", NO_NEWLINE],
+        "tests/expected/no_newline.txt.c+8.out",
+    )
+}
+
+#[test]
+fn ten_stdin() -> TestResult {
+    run_stdin(&[], TEN, "tests/expected/ten.txt.out")
+}
+
+#[test]
+fn ten_n3_stdin() -> TestResult {
+    run_stdin(&["-n", "3"], TEN, "tests/expected/ten.txt.n3.out")
+}
+
+#[test]
+fn ten_n_plus3_stdin() -> TestResult {
+    run_stdin(&["-n", "+3"], TEN, "tests/expected/ten.txt.n+3.out")
+}
+
+#[test]
+fn ten_c3_stdin() -> TestResult {
+    run_stdin(&["-c", "3"], TEN, "tests/expected/ten.txt.c3.out")
+}
+
+#[test]
+fn ten_c_plus8_stdin() -> TestResult {
+    run_stdin(&["-c", "+8"], TEN, "tests/expected/ten.txt.c+8.out")
+}
+
+#[test]
+fn no_newline_n3_stdin() -> TestResult {
+    run_stdin(
+        &["-n", "3"],
+        NO_NEWLINE,
+        "tests/expected/no_newline.txt.n3.out",
+    )
+}
+
+#[test]
+fn no_newline_c8_stdin() -> TestResult {
+    run_stdin(
+        &["-c", "8"],
+        NO_NEWLINE,
+        "tests/expected/no_newline.txt.c8.out",
+    )
+}
+
+#[test]
+fn multiple_files() -> TestResult {
+    run(&[EMPTY, ONE, TWO, THREE, TEN], "tests/expected/all.out")
+}
+
+#[test]
+fn multiple_files_n1() -> TestResult {
+    run(
+        &["-n", "1", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n1.out",
+    )
+}
+
+#[test]
+fn multiple_files_n_plus3() -> TestResult {
+    run(
+        &["-n", "+3", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.n+3.out",
+    )
+}
+
+#[test]
+fn multiple_files_c3() -> TestResult {
+    run(
+        &["-c", "3", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.c3.out",
+    )
+}
+
+#[test]
+fn multiple_files_c_plus8() -> TestResult {
+    run(
+        &["-c", "+8", EMPTY, ONE, TWO, THREE, TEN],
+        "tests/expected/all.c+8.out",
+    )
+}
+
+struct Follower {
+    child: Child,
+    output: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl Follower {
+    fn spawn(args: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut child = StdCommand::new(cargo_bin(PROG))
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut stdout = child.stdout.take().unwrap();
+        let (tx, output) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            while let Ok(bytes) = stdout.read(&mut buffer) {
+                if bytes == 0 || tx.send(buffer[..bytes].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            output,
+            buffer: vec![],
+        })
+    }
+
+    fn expect(&mut self, expected: &str) -> TestResult {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while self.buffer != expected.as_bytes() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.output.recv_timeout(timeout) {
+                Ok(chunk) => self.buffer.extend(chunk),
+                Err(_) => break,
+            }
+        }
+        assert_eq!(String::from_utf8_lossy(&self.buffer), expected);
+        Ok(())
+    }
+}
+
+impl Drop for Follower {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn append(path: &Path, text: &str) -> TestResult {
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?
+        .write_all(text.as_bytes())?;
+    Ok(())
+}
+
+fn follows_appended_lines(args: &[&str]) -> TestResult {
+    let dir = TempDir::new()?;
+    let log = dir.path().join("log");
+    append(&log, "one\ntwo\n")?;
+
+    let mut tailr = Follower::spawn(&[args, &[log.to_str().unwrap()]].concat())?;
+    tailr.expect("one\ntwo\n")?;
+
+    append(&log, "three\n")?;
+    tailr.expect("one\ntwo\nthree\n")?;
+
+    append(&log, "four\n")?;
+    tailr.expect("one\ntwo\nthree\nfour\n")
+}
+
+#[test]
+fn follow() -> TestResult {
+    follows_appended_lines(&["-f"])
+}
+
+#[test]
+fn follow_inotify() -> TestResult {
+    follows_appended_lines(&["-f", "--inotify"])
+}
+
+#[test]
+fn follow_name() -> TestResult {
+    follows_appended_lines(&["-F"])
+}
+
+#[test]
+fn follow_lines() -> TestResult {
+    let dir = TempDir::new()?;
+    let log = dir.path().join("log");
+    append(&log, "one\ntwo\nthree\n")?;
+
+    let mut tailr = Follower::spawn(&["-n", "1", "-f", log.to_str().unwrap()])?;
+    tailr.expect("three\n")?;
+
+    append(&log, "four\n")?;
+    tailr.expect("three\nfour\n")
+}
+
+fn follow_name_rotated(args: &[&str]) -> TestResult {
+    let dir = TempDir::new()?;
+    let log = dir.path().join("log");
+    append(&log, "one\n")?;
+
+    let mut tailr = Follower::spawn(&[args, &[log.to_str().unwrap()]].concat())?;
+    tailr.expect("one\n")?;
+
+    fs::rename(&log, dir.path().join("log.1"))?;
+    append(&log, "two\n")?;
+    tailr.expect("one\ntwo\n")?;
+
+    append(&log, "three\n")?;
+    tailr.expect("one\ntwo\nthree\n")
+}
+
+#[test]
+fn follow_name_rotated_poll() -> TestResult {
+    follow_name_rotated(&["-F"])
+}
+
+#[test]
+fn follow_name_rotated_inotify() -> TestResult {
+    follow_name_rotated(&["-F", "--inotify"])
+}
+
+#[test]
+fn follow_name_appears() -> TestResult {
+    let dir = TempDir::new()?;
+    let log = dir.path().join("log");
+
+    let mut tailr = Follower::spawn(&["-F", log.to_str().unwrap()])?;
+    thread::sleep(Duration::from_millis(200));
+
+    append(&log, "one\n")?;
+    tailr.expect("one\n")
+}
+
+#[test]
+fn follow_truncated() -> TestResult {
+    let dir = TempDir::new()?;
+    let log = dir.path().join("log");
+    append(&log, "one\ntwo\n")?;
+
+    let mut tailr = Follower::spawn(&["-f", log.to_str().unwrap()])?;
+    tailr.expect("one\ntwo\n")?;
+
+    File::create(&log)?;
+    thread::sleep(Duration::from_millis(1500));
+    append(&log, "three\n")?;
+    tailr.expect("one\ntwo\nthree\n")
+}
+
+#[test]
+fn follow_multiple_files() -> TestResult {
+    let dir = TempDir::new()?;
+    let log1 = dir.path().join("log1");
+    let log2 = dir.path().join("log2");
+    append(&log1, "one\n")?;
+    append(&log2, "two\n")?;
+
+    let (name1, name2) = (log1.to_str().unwrap(), log2.to_str().unwrap());
+    let mut tailr = Follower::spawn(&["-f", name1, name2])?;
+    let mut expected = format!("==> {} <==\none\n\n==> {} <==\ntwo\n", name1, name2);
+    tailr.expect(&expected)?;
+
+    append(&log2, "three\n")?;
+    expected.push_str("three\n");
+    tailr.expect(&expected)?;
+
+    append(&log1, "four\n")?;
+    expected.push_str(&format!("\n==> {} <==\nfour\n", name1));
+    tailr.expect(&expected)
+}
+
+#[test]
+fn follow_pid() -> TestResult {
+    let dir = TempDir::new()?;
+    let log = dir.path().join("log");
+    append(&log, "one\n")?;
+
+    let mut writer = StdCommand::new("sh")
+        .arg("-c")
+        .arg(format!("sleep 0.5; echo two >> '{}'", log.display()))
+        .spawn()?;
+    let pid = writer.id().to_string();
+    let writer = thread::spawn(move || writer.wait());
+
+    Command::cargo_bin(PROG)?
+        .args(["-f", "-s", "0.1", "--pid", &pid, log.to_str().unwrap()])
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .success()
+        .stdout("one\ntwo\n");
+
+    writer.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn dies_bad_sleep_interval() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-f", "-s", "foo", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid number of seconds -- foo"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_pid() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-f", "--pid", "foo", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid PID -- foo"));
+
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args([TEN])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_follow() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["-f", TEN])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn null_data() -> TestResult {
+    let dir = TempDir::new()?;
+    let file = dir.path().join("records");
+    fs::write(&file, "one\ntwo\0three\0four\0")?;
+    let file = file.to_str().unwrap();
+
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-n", "2", file])
+        .assert()
+        .success()
+        .stdout("three\0four\0");
+
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-n", "+2", file])
+        .assert()
+        .success()
+        .stdout("three\0four\0");
+
+    Ok(())
+}
+
+#[test]
+fn null_data_stdin() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--null-data", "-n", "1"])
+        .write_stdin("one\ntwo\0three\0four\n")
+        .assert()
+        .success()
+        .stdout("four\n");
+    Ok(())
+}
+
+#[test]
+fn size_suffixes() -> TestResult {
+    run(&["-c", "1K", TEN], "tests/expected/ten.txt.out")?;
+    run(&["-n", "-1KiB", TEN], "tests/expected/ten.txt.out")?;
+    run(&["-c", "+1b", TEN], "tests/expected/empty.txt.out")
+}
+
+#[test]
+fn dies_count_too_large() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-n", "+1Y", EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("line count too large -- +1Y"));
+    Ok(())
+}
@@ -1,9 +1,7 @@
 use clap::Parser;
-use std::error::Error;
+use clr_common::{open_input, read_record, terminator, AppResult};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
-
-type AppResult<T> = Result<T, Box<dyn Error>>;
+use std::io::{BufWriter, Write};
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -23,16 +21,25 @@ pub struct App {
     /// Show counts
     #[clap(short = 'c', long = "count", takes_value = false)]
     count: bool,
+
+    /// Decompress gzip, bzip2, xz and zstd input
+    #[clap(short = 'Z', long = "decompress", takes_value = false)]
+    decompress: bool,
+
+    /// Split and write records on NUL instead of newline
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
 }
 
 impl App {
     pub fn run(self) -> AppResult<()> {
-        let mut in_file = open(&self.in_file).map_err(|e| format!("{}: {}", self.in_file, e))?;
+        let mut in_file = open_input(&self.in_file, self.decompress)?;
         let mut out_file: Box<dyn Write> = match &self.out_file {
-            Some(filename) => Box::new(File::create(filename)?),
-            _ => Box::new(io::stdout()),
+            Some(filename) => Box::new(BufWriter::new(File::create(filename)?)),
+            _ => Box::new(clr_common::stdout()),
         };
 
+        let terminator = terminator(self.null_data);
         let mut line = String::new();
         let mut previous = String::new();
         let mut count: u64 = 0;
@@ -49,13 +56,13 @@ impl App {
         };
 
         loop {
-            let bytes = in_file.read_line(&mut line)?;
+            let bytes = read_record(&mut in_file, terminator, &mut line)?;
 
             if bytes == 0 {
                 break;
             }
 
-            if line.trim_end() != previous.trim_end() {
+            if trim(&line, terminator) != trim(&previous, terminator) {
                 print(count, &previous)?;
                 previous = line.clone();
                 count = 0;
@@ -66,14 +73,14 @@ impl App {
         }
 
         print(count, &previous)?;
+        out_file.flush()?;
 
         Ok(())
     }
 }
 
-fn open(filename: &str) -> AppResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
+/// Strips the terminator and any trailing whitespace, which are ignored when
+/// comparing records
+fn trim(record: &str, terminator: u8) -> &str {
+    record.trim_end_matches(char::from(terminator)).trim_end()
 }
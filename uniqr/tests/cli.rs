@@ -0,0 +1,60 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use std::error::Error;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+
+const PROG: &str = "uniqr";
+const REPEAT: &str = "tests/inputs/repeat.txt";
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["Cargo.toml"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn decompress() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-Z", "-c", &format!("{}.xz", REPEAT)])
+        .assert()
+        .success()
+        .stdout("   2 a\n   1 b\n   3 c\n   1 a\n");
+
+    Ok(())
+}
+
+#[test]
+fn null_data() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-c"])
+        .write_stdin("a\nb\0a\nb\0c\0")
+        .assert()
+        .success()
+        .stdout("   2 a\nb\0   1 c\0");
+    Ok(())
+}
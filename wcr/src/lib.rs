@@ -1,9 +1,62 @@
 use clap::Parser;
-use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use clr_common::{open, open_input, terminator, AppResult, Error, JsonWriter, Named};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, Write};
+use std::iter;
+use std::os::unix::ffi::OsStringExt;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-type AppResult<T> = Result<T, Box<dyn Error>>;
+/// Columns between tab stops, as in GNU `wc -L`
+const TAB_WIDTH: usize = 8;
+
+/// Character encoding of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Bytes that are not valid UTF-8 are not counted as characters
+    Utf8,
+    /// Every byte is a character
+    Latin1,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "latin1" | "iso-8859-1" => Ok(Self::Latin1),
+            _ => Err(format!("unsupported encoding: {}", s)),
+        }
+    }
+}
+
+/// How `count` splits the input into lines, characters and words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counting {
+    /// Byte that ends a line
+    pub terminator: u8,
+    pub encoding: Encoding,
+    /// Count extended grapheme clusters instead of Unicode scalar values
+    pub graphemes: bool,
+    /// Count words by Unicode word boundaries (UAX #29) instead of
+    /// whitespace
+    pub unicode_words: bool,
+}
+
+impl Default for Counting {
+    fn default() -> Self {
+        Self {
+            terminator: b'\n',
+            encoding: Encoding::Utf8,
+            graphemes: false,
+            unicode_words: false,
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -12,10 +65,23 @@ type AppResult<T> = Result<T, Box<dyn Error>>;
     about = "Rust wc"
 )]
 pub struct App {
-    /// Input file(s)
-    #[clap(value_name = "FILE", multiple_occurrences = true, default_value = "-")]
+    /// Input file(s) [default: -]
+    #[clap(value_name = "FILE", multiple_occurrences = true)]
     files: Vec<String>,
 
+    /// Read NUL-separated input file names from F ('-' for standard input)
+    #[clap(
+        long = "files0-from",
+        value_name = "F",
+        conflicts_with_all = &["files", "files-from"]
+    )]
+    files0_from: Option<String>,
+
+    /// Read newline-separated input file names from F ('-' for standard
+    /// input)
+    #[clap(long = "files-from", value_name = "F", conflicts_with = "files")]
+    files_from: Option<String>,
+
     /// Show line count
     #[clap(short = 'l', long = "lines", takes_value = false)]
     lines: bool,
@@ -36,13 +102,61 @@ pub struct App {
         conflicts_with = "bytes"
     )]
     chars: bool,
+
+    /// Show the display width of the longest line
+    #[clap(short = 'L', long = "max-line-length", takes_value = false)]
+    max_line_length: bool,
+
+    /// Character encoding for counting characters: utf-8 or latin1
+    #[clap(long = "encoding", value_name = "ENCODING", default_value = "utf-8")]
+    encoding: Encoding,
+
+    /// Count extended grapheme clusters as characters; implies -m
+    #[clap(long = "graphemes", takes_value = false, conflicts_with = "bytes")]
+    graphemes: bool,
+
+    /// Count words by Unicode word boundaries (UAX #29), leaving out
+    /// punctuation, instead of splitting on whitespace
+    #[clap(long = "unicode-words", takes_value = false)]
+    unicode_words: bool,
+
+    /// Decompress gzip, bzip2, xz and zstd input
+    #[clap(short = 'Z', long = "decompress", takes_value = false)]
+    decompress: bool,
+
+    /// Print all counts as a JSON array of records
+    #[clap(long = "json", takes_value = false, conflicts_with = "ndjson")]
+    json: bool,
+
+    /// Print all counts as one JSON record per line
+    #[clap(long = "ndjson", takes_value = false)]
+    ndjson: bool,
+
+    /// Count NUL-terminated records as lines
+    #[clap(short = 'z', long = "null-data", takes_value = false)]
+    null_data: bool,
+}
+
+/// A JSON record of the counts of one file, or of the total if `file` is null
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    file: Option<&'a str>,
+    #[serde(flatten)]
+    info: &'a FileInfo,
 }
 
 impl App {
     pub fn run(mut self) -> AppResult<()> {
-        if [self.lines, self.words, self.bytes, self.chars]
-            .iter()
-            .all(|v| !v)
+        self.chars |= self.graphemes;
+        if [
+            self.lines,
+            self.words,
+            self.bytes,
+            self.chars,
+            self.max_line_length,
+        ]
+        .iter()
+        .all(|v| !v)
         {
             self.lines = true;
             self.words = true;
@@ -53,61 +167,158 @@ impl App {
         let mut total_words = 0;
         let mut total_bytes = 0;
         let mut total_chars = 0;
+        let mut total_max_line_length = 0;
+
+        let mut out = clr_common::stdout();
+        let mut json = (self.json || self.ndjson).then(|| JsonWriter::new(self.ndjson));
+        let counting = Counting {
+            terminator: terminator(self.null_data),
+            encoding: self.encoding,
+            graphemes: self.graphemes,
+            unicode_words: self.unicode_words,
+        };
+        let mut file_count = 0;
+        let mut failed = false;
+
+        for path in self.operands()? {
+            let path = path?;
+            if let Err(e) = self.check_operand(&path) {
+                eprintln!("{}", e);
+                failed = true;
+                continue;
+            }
+            file_count += 1;
+            let filename = path.to_string_lossy();
 
-        for filename in &self.files {
-            match open(filename) {
+            match open_input(&path, self.decompress) {
                 Err(e) => {
-                    eprintln!("{}: {}", filename, e)
+                    eprintln!("{}", e);
+                    failed = true;
                 }
 
-                Ok(file) => {
-                    if let Ok(FileInfo {
-                        lines,
-                        words,
-                        bytes,
-                        chars,
-                    }) = count(file)
-                    {
-                        println!(
-                            "{}{}{}{}{}",
-                            format_field(lines, self.lines),
-                            format_field(words, self.words),
-                            format_field(bytes, self.bytes),
-                            format_field(chars, self.chars),
-                            if filename == "-" {
-                                "".to_string()
-                            } else {
-                                format!(" {}", filename)
-                            }
-                        );
-
-                        total_lines += lines;
-                        total_words += words;
-                        total_bytes += bytes;
-                        total_chars += chars;
+                Ok(file) => match count(file, &counting) {
+                    Err(e) => {
+                        eprintln!("{}: {}", filename, e);
+                        failed = true;
                     }
-                }
+
+                    Ok(info) => {
+                        if let Some(json) = &mut json {
+                            let record = Record {
+                                file: Some(&filename),
+                                info: &info,
+                            };
+                            json.write(&mut out, &record)?;
+                        } else {
+                            writeln!(
+                                out,
+                                "{}{}{}{}{}{}",
+                                format_field(info.lines, self.lines),
+                                format_field(info.words, self.words),
+                                format_field(info.bytes, self.bytes),
+                                format_field(info.chars, self.chars),
+                                format_field(info.max_line_length, self.max_line_length),
+                                if filename == "-" {
+                                    "".to_string()
+                                } else {
+                                    format!(" {}", filename)
+                                }
+                            )?;
+                        }
+
+                        total_lines += info.lines;
+                        total_words += info.words;
+                        total_bytes += info.bytes;
+                        total_chars += info.chars;
+                        total_max_line_length = total_max_line_length.max(info.max_line_length);
+                    }
+                },
             }
         }
 
-        if self.files.len() > 1 {
-            println!(
-                "{}{}{}{} total",
+        if let Some(mut json) = json {
+            if file_count > 1 {
+                let total = FileInfo {
+                    lines: total_lines,
+                    words: total_words,
+                    bytes: total_bytes,
+                    chars: total_chars,
+                    max_line_length: total_max_line_length,
+                };
+                let record = Record {
+                    file: None,
+                    info: &total,
+                };
+                json.write(&mut out, &record)?;
+            }
+            json.finish(&mut out)?;
+        } else if file_count > 1 {
+            writeln!(
+                out,
+                "{}{}{}{}{} total",
                 format_field(total_lines, self.lines),
                 format_field(total_words, self.words),
                 format_field(total_bytes, self.bytes),
-                format_field(total_chars, self.chars)
-            );
+                format_field(total_chars, self.chars),
+                format_field(total_max_line_length, self.max_line_length)
+            )?;
         }
 
+        out.flush()?;
+
+        if failed {
+            return Err(Error::Exit(1));
+        }
         Ok(())
     }
-}
 
-fn open(filename: &str) -> AppResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    /// The input file names, read one at a time with --files0-from or
+    /// --files-from so that any number of files can be counted. Names from a
+    /// list are taken as raw bytes, as they need not be valid UTF-8.
+    fn operands(&self) -> AppResult<Box<dyn Iterator<Item = io::Result<OsString>>>> {
+        let (list, terminator) = match (&self.files0_from, &self.files_from) {
+            (Some(list), _) => (list, b'\0'),
+            (None, Some(list)) => (list, b'\n'),
+            (None, None) if self.files.is_empty() => {
+                return Ok(Box::new(iter::once(Ok(OsString::from("-")))))
+            }
+            (None, None) => {
+                let files = self.files.clone().into_iter().map(OsString::from);
+                return Ok(Box::new(files.map(Ok)));
+            }
+        };
+
+        let mut file = Named::new(list, open(list)?);
+        let names = iter::from_fn(move || {
+            let mut name = vec![];
+            match file.read_until(terminator, &mut name) {
+                Ok(0) => None,
+                Ok(_) => {
+                    if name.last() == Some(&terminator) {
+                        name.pop();
+                    }
+                    Some(Ok(OsString::from_vec(name)))
+                }
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::new(names))
+    }
+
+    /// Rejects file names from a list that cannot name an input
+    fn check_operand(&self, filename: &OsStr) -> Result<(), String> {
+        let list = match self.files0_from.as_ref().or(self.files_from.as_ref()) {
+            Some(list) => list,
+            None => return Ok(()),
+        };
+
+        if filename.is_empty() {
+            Err(format!("{}: invalid zero-length file name", list))
+        } else if filename == "-" && list == "-" {
+            Err("when reading file names from standard input, no file name of '-' allowed".into())
+        } else {
+            Ok(())
+        }
     }
 }
 
@@ -119,33 +330,49 @@ fn format_field(value: usize, show: bool) -> String {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
 pub struct FileInfo {
     lines: usize,
     words: usize,
     bytes: usize,
     chars: usize,
+    max_line_length: usize,
 }
 
-pub fn count(mut file: impl BufRead) -> AppResult<FileInfo> {
+/// Counts the lines, words, bytes and characters of `file`. Lines are read as
+/// bytes, so any input can be counted.
+pub fn count(mut file: impl BufRead, counting: &Counting) -> AppResult<FileInfo> {
     let mut lines = 0;
     let mut words = 0;
     let mut bytes = 0;
     let mut chars = 0;
+    let mut max_line_length = 0;
 
-    let mut line = String::new();
+    let mut line = vec![];
 
     loop {
-        let line_bytes = file.read_line(&mut line)?;
+        let line_bytes = file.read_until(counting.terminator, &mut line)?;
 
         if line_bytes == 0 {
             break;
         }
 
+        let text = decode(&line, counting.encoding);
         lines += 1;
-        words += line.split_whitespace().count();
+        words += match counting.unicode_words {
+            true => text.unicode_words().count(),
+            false => text.split_whitespace().count(),
+        };
         bytes += line_bytes;
-        chars += line.chars().count();
+        chars += match (counting.graphemes, counting.encoding) {
+            (true, _) => text.graphemes(true).count(),
+            (false, Encoding::Utf8) => line
+                .utf8_chunks()
+                .map(|chunk| chunk.valid().chars().count())
+                .sum(),
+            (false, Encoding::Latin1) => line_bytes,
+        };
+        max_line_length = max_line_length.max(display_width(&text));
 
         line.clear();
     }
@@ -155,12 +382,48 @@ pub fn count(mut file: impl BufRead) -> AppResult<FileInfo> {
         words,
         bytes,
         chars,
+        max_line_length,
     })
 }
 
+/// The display width of `line` as in GNU `wc -L`: tabs advance to the next
+/// tab stop, carriage returns and form feeds return to the first column,
+/// other control characters take no space and wide characters take two
+/// columns
+fn display_width(line: &str) -> usize {
+    let mut max = 0;
+    let mut column = 0;
+    let mut start = 0;
+
+    for (i, c) in line.char_indices().filter(|(_, c)| c.is_control()) {
+        column += line[start..i].width();
+        start = i + c.len_utf8();
+
+        match c {
+            '\t' => column += TAB_WIDTH - column % TAB_WIDTH,
+            '\r' | '\x0c' => {
+                max = max.max(column);
+                column = 0;
+            }
+            _ => (),
+        }
+    }
+
+    max.max(column + line[start..].width())
+}
+
+/// Decodes `line` for splitting it into words and graphemes. Invalid UTF-8 is
+/// replaced, so that it still separates and belongs to words.
+fn decode(line: &[u8], encoding: Encoding) -> Cow<'_, str> {
+    match encoding {
+        Encoding::Utf8 => String::from_utf8_lossy(line),
+        Encoding::Latin1 => Cow::Owned(line.iter().map(|&b| char::from(b)).collect()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{count, format_field, FileInfo};
+    use super::{count, display_width, format_field, Counting, Encoding, FileInfo};
     use std::io::Cursor;
 
     #[test]
@@ -173,16 +436,119 @@ mod tests {
     #[test]
     fn test_count() {
         assert_eq!(
-            count(Cursor::new(
-                "I don't want the world. I just want your half.\r\n"
-            ))
+            count(
+                Cursor::new("I don't want the world. I just want your half.\r\n"),
+                &Counting::default()
+            )
             .unwrap(),
             FileInfo {
                 lines: 1,
                 words: 10,
                 bytes: 48,
                 chars: 48,
+                max_line_length: 46,
+            }
+        );
+
+        assert_eq!(
+            count(
+                Cursor::new("one\ntwo\0three\0"),
+                &Counting {
+                    terminator: b'\0',
+                    ..Counting::default()
+                }
+            )
+            .unwrap(),
+            FileInfo {
+                lines: 2,
+                words: 3,
+                bytes: 14,
+                chars: 14,
+                max_line_length: 6,
+            }
+        );
+    }
+
+    fn info(text: &[u8], counting: Counting) -> FileInfo {
+        count(Cursor::new(text), &counting).unwrap()
+    }
+
+    #[test]
+    fn test_count_binary() {
+        let text = b"\xff\xfe caf\xc3\xa9 \x00\x80\n";
+        assert_eq!(
+            info(text, Counting::default()),
+            FileInfo {
+                lines: 1,
+                words: 3,
+                bytes: 12,
+                chars: 8,
+                max_line_length: 9,
             }
         );
+
+        let latin1 = Counting {
+            encoding: Encoding::Latin1,
+            ..Counting::default()
+        };
+        assert_eq!(info(text, latin1).chars, 12);
+    }
+
+    #[test]
+    fn test_count_cjk() {
+        let text = "日本語のテキスト、二行目。\n中文 文本\n".as_bytes();
+        assert_eq!(
+            info(text, Counting::default()),
+            FileInfo {
+                lines: 2,
+                words: 3,
+                bytes: 54,
+                chars: 20,
+                max_line_length: 26,
+            }
+        );
+
+        let unicode_words = Counting {
+            unicode_words: true,
+            ..Counting::default()
+        };
+        // Ideographs and hiragana are words of their own, while katakana are
+        // grouped and punctuation is left out
+        assert_eq!(info(text, unicode_words).words, 12);
+    }
+
+    #[test]
+    fn test_count_emoji() {
+        // A thumbs up with a skin tone, a family joined by zero-width joiners
+        // and a flag, with combining accents
+        let text = "👍🏽 👨‍👩‍👧 🇯🇵 e\u{301}te\u{301}\n".as_bytes();
+        let counted = info(text, Counting::default());
+        assert_eq!(counted.words, 4);
+        assert_eq!(counted.chars, 18);
+
+        let graphemes = Counting {
+            graphemes: true,
+            ..Counting::default()
+        };
+        assert_eq!(info(text, graphemes).chars, 10);
+
+        let unicode_words = Counting {
+            unicode_words: true,
+            ..Counting::default()
+        };
+        assert_eq!(info(text, unicode_words).words, 1);
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width(""), 0);
+        assert_eq!(display_width("hello\n"), 5);
+        assert_eq!(display_width("\t"), 8);
+        assert_eq!(display_width("abc\tde\tf"), 17);
+        assert_eq!(display_width("12345678\t"), 16);
+        assert_eq!(display_width("日本語\tx"), 9);
+        assert_eq!(display_width("long line\rshort"), 9);
+        assert_eq!(display_width("a\x07\x1bb"), 2);
+        assert_eq!(display_width("👨\u{200d}👩\u{200d}👧 e\u{301}"), 4);
     }
 }
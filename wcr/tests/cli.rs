@@ -0,0 +1,290 @@
+use assert_cmd::{cargo::cargo_bin, Command};
+use predicates::prelude::*;
+use rand::{distributions::Alphanumeric, Rng};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::process::{Command as StdCommand, Stdio};
+
+const PROG: &str = "wcr";
+const FOX: &str = "tests/inputs/fox.txt";
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+#[test]
+fn usage() -> TestResult {
+    for option in &["-h", "--help"] {
+        Command::cargo_bin(PROG)?
+            .arg(option)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("USAGE"));
+    }
+    Ok(())
+}
+
+#[test]
+fn broken_pipe() -> TestResult {
+    let (reader, writer) = io::pipe()?;
+    drop(reader);
+
+    let output = StdCommand::new(cargo_bin(PROG))
+        .args(["Cargo.toml"])
+        .stdin(Stdio::null())
+        .stdout(writer)
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+fn gen_bad_file() -> String {
+    loop {
+        let filename: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+#[test]
+fn decompress() -> TestResult {
+    let gz = format!("{}.gz", FOX);
+    Command::cargo_bin(PROG)?
+        .args(["-Z", &gz])
+        .assert()
+        .success()
+        .stdout(format!("       1       9      45 {}\n", gz));
+
+    Ok(())
+}
+
+#[test]
+fn json() -> TestResult {
+    let expected = format!(
+        "[\n{0},\n{0},\n{1}\n]\n",
+        format!(
+            r#"{{"file":"{}","lines":1,"words":9,"bytes":45,"chars":45,"max_line_length":44}}"#,
+            FOX
+        ),
+        r#"{"file":null,"lines":2,"words":18,"bytes":90,"chars":90,"max_line_length":44}"#
+    );
+
+    Command::cargo_bin(PROG)?
+        .args(["--json", FOX, FOX])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn ndjson() -> TestResult {
+    let input = fs::read_to_string(FOX)?;
+    Command::cargo_bin(PROG)?
+        .args(["--ndjson", "-l"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("{\"file\":\"-\",\"lines\":1,\"words\":9,\"bytes\":45,\"chars\":45,\"max_line_length\":44}\n");
+
+    Ok(())
+}
+
+#[test]
+fn skips_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", bad);
+
+    Command::cargo_bin(PROG)?
+        .args([&bad, FOX])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(FOX))
+        .stderr(predicate::str::is_match(expected)?);
+
+    Ok(())
+}
+
+#[test]
+fn null_data() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-z", "-l"])
+        .write_stdin("one\ntwo\0three\0")
+        .assert()
+        .success()
+        .stdout("       2\n");
+    Ok(())
+}
+
+#[test]
+fn binary() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .write_stdin(&b"\xff\xfe\x00 binary\n\x80"[..])
+        .assert()
+        .success()
+        .stdout("       2       3      12\n");
+
+    Command::cargo_bin(PROG)?
+        .arg("-m")
+        .write_stdin(&b"caf\xc3\xa9 \xff\n"[..])
+        .assert()
+        .success()
+        .stdout("       6\n");
+    Ok(())
+}
+
+#[test]
+fn encoding() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-m", "--encoding", "latin1"])
+        .write_stdin(&b"caf\xe9\n"[..])
+        .assert()
+        .success()
+        .stdout("       5\n");
+
+    Command::cargo_bin(PROG)?
+        .args(["-m", "--encoding", "ebcdic"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unsupported encoding: ebcdic"));
+    Ok(())
+}
+
+#[test]
+fn graphemes() -> TestResult {
+    let input = "👨‍👩‍👧 e\u{301}\n";
+    Command::cargo_bin(PROG)?
+        .arg("-m")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("       9\n");
+
+    Command::cargo_bin(PROG)?
+        .arg("--graphemes")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("       4\n");
+    Ok(())
+}
+
+#[test]
+fn unicode_words() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-w", "--unicode-words"])
+        .write_stdin("Hello, world! -- 日本語\n")
+        .assert()
+        .success()
+        .stdout("       5\n");
+    Ok(())
+}
+
+#[test]
+fn max_line_length() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["-L", FOX])
+        .assert()
+        .success()
+        .stdout(format!("      44 {}\n", FOX));
+
+    Command::cargo_bin(PROG)?
+        .args(["--max-line-length", "-l"])
+        .write_stdin("a\tb\n日本語\nlonger line\r\n")
+        .assert()
+        .success()
+        .stdout("       3      11\n");
+    Ok(())
+}
+
+#[test]
+fn max_line_length_total() -> TestResult {
+    // The total is the longest of all lines, not the sum
+    Command::cargo_bin(PROG)?
+        .args(["-L", "Cargo.toml", FOX])
+        .assert()
+        .success()
+        .stdout(format!(
+            "      96 Cargo.toml\n      44 {}\n      96 total\n",
+            FOX
+        ));
+    Ok(())
+}
+
+#[test]
+fn files0_from() -> TestResult {
+    let expected = format!(
+        "       1       9      45 {fox}\n       1       9      45 {fox}\n       2      18      90 total\n",
+        fox = FOX
+    );
+    Command::cargo_bin(PROG)?
+        .args(["--files0-from", "-"])
+        .write_stdin(format!("{}\0{}\0", FOX, FOX))
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn files_from() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--files-from", "tests/inputs/files.txt"])
+        .assert()
+        .success()
+        .stdout(format!("       1       9      45 {}\n", FOX));
+    Ok(())
+}
+
+#[test]
+fn files0_from_bad_names() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--files0-from=-", "-l"])
+        .write_stdin(format!("-\0\0{}\0", FOX))
+        .assert()
+        .code(1)
+        .stdout(format!("       1 {}\n", FOX))
+        .stderr(
+            "when reading file names from standard input, no file name of '-' allowed\n\
+             -: invalid zero-length file name\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn dies_files_from_with_operands() -> TestResult {
+    Command::cargo_bin(PROG)?
+        .args(["--files0-from", "-", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn files0_from_non_utf8_name() -> TestResult {
+    let mut list = format!("{}\0", FOX).into_bytes();
+    list.extend(b"\xff\0");
+    list.extend(format!("{}\0", FOX).into_bytes());
+
+    let expected = format!(
+        "       1       9      45 {fox}\n       1       9      45 {fox}\n       2      18      90 total\n",
+        fox = FOX
+    );
+    Command::cargo_bin(PROG)?
+        .args(["--files0-from", "-"])
+        .write_stdin(list)
+        .assert()
+        .code(1)
+        .stdout(expected)
+        .stderr(predicate::str::starts_with("\u{fffd}: "));
+    Ok(())
+}